@@ -24,4 +24,40 @@ impl AABB {
             && self.mins.z < other.maxs.z
             && self.maxs.z > other.mins.z
     }
+
+    // 광선 (origin + t * direction)이 상자에 들어가는 t와 그때 지나는 면의 축 (안에서 시작하면 None)
+    pub fn ray_intersection(
+        &self,
+        origin: &Vec3,
+        direction: &Vec3,
+    ) -> Option<(f32, Option<usize>)> {
+        let mut t_enter = 0.0f32;
+        let mut t_exit = f32::INFINITY;
+        let mut axis_entered = None;
+
+        for axis in 0..3 {
+            if direction[axis] == 0.0 {
+                if origin[axis] < self.mins[axis] || origin[axis] > self.maxs[axis] {
+                    return None;
+                }
+                continue;
+            }
+
+            let t0 = (self.mins[axis] - origin[axis]) / direction[axis];
+            let t1 = (self.maxs[axis] - origin[axis]) / direction[axis];
+            let (near, far) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+
+            if near > t_enter {
+                t_enter = near;
+                axis_entered = Some(axis);
+            }
+            t_exit = t_exit.min(far);
+
+            if t_enter > t_exit {
+                return None;
+            }
+        }
+
+        Some((t_enter, axis_entered))
+    }
 }
@@ -0,0 +1,286 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+// 할당 하나를 가리키는 번호 - compact가 할당을 옮겨도 바뀌지 않는다
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct AllocationId(u32);
+
+// compact가 정한 할당의 새 자리 - 데이터를 from에서 to로 size만큼 복사해야 한다
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Relocation {
+    pub id: AllocationId,
+    pub from: usize,
+    pub to: usize,
+    pub size: usize,
+}
+
+// 큰 버퍼 하나를 여러 메시가 나눠 쓰도록 구간을 나눠 주는 할당기 - GL은 쓰지 않는다
+pub struct ArenaAllocator {
+    capacity: usize,
+    // 빈 구간의 시작 위치 -> 길이 (붙어 있는 빈 구간은 항상 하나로 합쳐져 있다)
+    free_ranges: BTreeMap<usize, usize>,
+    allocations: HashMap<AllocationId, Range<usize>>,
+    next_id: u32,
+}
+
+impl ArenaAllocator {
+    pub fn new(capacity: usize) -> Self {
+        let mut free_ranges = BTreeMap::new();
+        if capacity > 0 {
+            free_ranges.insert(0, capacity);
+        }
+
+        Self {
+            capacity,
+            free_ranges,
+            allocations: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn free_space(&self) -> usize {
+        self.free_ranges.values().sum()
+    }
+
+    pub fn used(&self) -> usize {
+        self.capacity - self.free_space()
+    }
+
+    pub fn allocation_count(&self) -> usize {
+        self.allocations.len()
+    }
+
+    pub fn largest_free_range(&self) -> usize {
+        self.free_ranges.values().copied().max().unwrap_or(0)
+    }
+
+    // 빈 공간 중 가장 큰 빈 구간 밖에 있는 비율 - 0이면 빈 공간이 한 덩어리로 모여 있다
+    pub fn fragmentation(&self) -> f32 {
+        let free = self.free_space();
+        if free == 0 {
+            return 0.0;
+        }
+        1.0 - self.largest_free_range() as f32 / free as f32
+    }
+
+    pub fn range(&self, id: AllocationId) -> Option<Range<usize>> {
+        self.allocations.get(&id).cloned()
+    }
+
+    // 들어갈 수 있는 가장 작은 빈 구간의 앞쪽을 쓴다 - 들어갈 구간이 없으면 None
+    pub fn allocate(&mut self, size: usize) -> Option<AllocationId> {
+        assert!(size > 0, "Allocations must not be empty");
+
+        let (start, length) = self
+            .free_ranges
+            .iter()
+            .filter(|(_, &length)| length >= size)
+            .min_by_key(|(&start, &length)| (length, start))
+            .map(|(&start, &length)| (start, length))?;
+
+        self.free_ranges.remove(&start);
+        if length > size {
+            self.free_ranges.insert(start + size, length - size);
+        }
+
+        let id = AllocationId(self.next_id);
+        self.next_id += 1;
+        self.allocations.insert(id, start..start + size);
+        Some(id)
+    }
+
+    pub fn free(&mut self, id: AllocationId) {
+        let range = self
+            .allocations
+            .remove(&id)
+            .expect("Freed an allocation that doesn't exist");
+        let (mut start, mut end) = (range.start, range.end);
+
+        // 바로 앞과 바로 뒤의 빈 구간과 합친다
+        let previous = self.free_ranges.range(..start).next_back();
+        if let Some((&previous_start, &previous_length)) = previous {
+            if previous_start + previous_length == start {
+                self.free_ranges.remove(&previous_start);
+                start = previous_start;
+            }
+        }
+        if let Some(next_length) = self.free_ranges.remove(&end) {
+            end += next_length;
+        }
+
+        self.free_ranges.insert(start, end - start);
+    }
+
+    // 버퍼를 늘린 만큼 끝에 빈 공간을 붙인다
+    pub fn grow(&mut self, capacity: usize) {
+        assert!(capacity >= self.capacity, "An arena can't shrink");
+        if capacity == self.capacity {
+            return;
+        }
+
+        let mut start = self.capacity;
+        let last = self.free_ranges.iter().next_back().map(|(&s, &l)| (s, l));
+        if let Some((last_start, last_length)) = last {
+            if last_start + last_length == self.capacity {
+                self.free_ranges.remove(&last_start);
+                start = last_start;
+            }
+        }
+
+        self.free_ranges.insert(start, capacity - start);
+        self.capacity = capacity;
+    }
+
+    // 할당들을 순서대로 버퍼 앞쪽에 빈틈없이 붙이고, 남은 공간을 끝의 빈 구간 하나로 만든다
+    pub fn compact(&mut self) -> Vec<Relocation> {
+        let mut allocations: Vec<(AllocationId, Range<usize>)> = self
+            .allocations
+            .iter()
+            .map(|(&id, range)| (id, range.clone()))
+            .collect();
+        allocations.sort_by_key(|(_, range)| range.start);
+
+        let mut relocations = Vec::with_capacity(allocations.len());
+        let mut offset = 0;
+        for (id, range) in allocations {
+            let size = range.len();
+            relocations.push(Relocation {
+                id,
+                from: range.start,
+                to: offset,
+                size,
+            });
+            self.allocations.insert(id, offset..offset + size);
+            offset += size;
+        }
+
+        self.free_ranges.clear();
+        if offset < self.capacity {
+            self.free_ranges.insert(offset, self.capacity - offset);
+        }
+
+        relocations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn free_ranges(allocator: &ArenaAllocator) -> Vec<(usize, usize)> {
+        allocator
+            .free_ranges
+            .iter()
+            .map(|(&start, &length)| (start, length))
+            .collect()
+    }
+
+    #[test]
+    fn allocate_picks_the_smallest_range_that_fits() {
+        let mut allocator = ArenaAllocator::new(100);
+        let ids: Vec<AllocationId> = [10, 30, 10, 15, 10]
+            .iter()
+            .map(|&size| allocator.allocate(size).unwrap())
+            .collect();
+        // 빈 구간: 10..40 (30), 50..65 (15), 75..100 (25)
+        allocator.free(ids[1]);
+        allocator.free(ids[3]);
+
+        let id = allocator.allocate(12).unwrap();
+        assert_eq!(allocator.range(id), Some(50..62));
+        let id = allocator.allocate(20).unwrap();
+        assert_eq!(allocator.range(id), Some(75..95));
+        assert!(allocator.allocate(31).is_none());
+    }
+
+    #[test]
+    fn free_merges_with_both_neighbours() {
+        let mut allocator = ArenaAllocator::new(30);
+        let ids: Vec<AllocationId> = (0..3).map(|_| allocator.allocate(10).unwrap()).collect();
+
+        allocator.free(ids[0]);
+        allocator.free(ids[2]);
+        assert_eq!(free_ranges(&allocator), vec![(0, 10), (20, 10)]);
+
+        allocator.free(ids[1]);
+        assert_eq!(free_ranges(&allocator), vec![(0, 30)]);
+        assert_eq!(allocator.allocation_count(), 0);
+    }
+
+    #[test]
+    fn grow_extends_a_trailing_free_range() {
+        let mut allocator = ArenaAllocator::new(20);
+        allocator.allocate(10).unwrap();
+        allocator.grow(40);
+        assert_eq!(free_ranges(&allocator), vec![(10, 30)]);
+        assert_eq!(allocator.capacity(), 40);
+    }
+
+    #[test]
+    fn grow_appends_a_range_after_an_allocation() {
+        let mut allocator = ArenaAllocator::new(20);
+        let first = allocator.allocate(10).unwrap();
+        allocator.allocate(10).unwrap();
+        allocator.free(first);
+        allocator.grow(40);
+        assert_eq!(free_ranges(&allocator), vec![(0, 10), (20, 20)]);
+    }
+
+    #[test]
+    fn compact_packs_allocations_to_the_front() {
+        let mut allocator = ArenaAllocator::new(100);
+        let ids: Vec<AllocationId> = [10, 20, 5, 15, 8]
+            .iter()
+            .map(|&size| allocator.allocate(size).unwrap())
+            .collect();
+        allocator.free(ids[0]);
+        allocator.free(ids[3]);
+        let sizes: Vec<usize> = ids
+            .iter()
+            .map(|&id| allocator.range(id).map_or(0, |r| r.len()))
+            .collect();
+
+        let relocations = allocator.compact();
+        assert!(relocations
+            .windows(2)
+            .all(|pair| pair[0].from < pair[1].from));
+        assert_eq!(
+            relocations
+                .iter()
+                .map(|r| (r.id, r.from, r.to))
+                .collect::<Vec<_>>(),
+            vec![(ids[1], 10, 0), (ids[2], 30, 20), (ids[4], 50, 25)]
+        );
+        assert_eq!(free_ranges(&allocator), vec![(33, 67)]);
+
+        for relocation in &relocations {
+            let range = allocator.range(relocation.id).unwrap();
+            assert_eq!(range, relocation.to..relocation.to + relocation.size);
+        }
+        for (i, &id) in ids.iter().enumerate() {
+            assert_eq!(allocator.range(id).map_or(0, |r| r.len()), sizes[i]);
+        }
+        assert_eq!(allocator.used(), 33);
+    }
+
+    #[test]
+    fn fragmentation() {
+        let mut allocator = ArenaAllocator::new(40);
+        assert_eq!(allocator.fragmentation(), 0.0);
+
+        let ids: Vec<AllocationId> = (0..4).map(|_| allocator.allocate(10).unwrap()).collect();
+        assert_eq!(allocator.fragmentation(), 0.0);
+
+        // 빈 구간 10, 10 - 가장 큰 구간 밖에 절반이 있다
+        allocator.free(ids[0]);
+        allocator.free(ids[2]);
+        assert_eq!(allocator.fragmentation(), 0.5);
+
+        allocator.compact();
+        assert_eq!(allocator.fragmentation(), 0.0);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::block_registry::BLOCKS;
+use crate::chunk::BlockID;
+use noise::{NoiseFn, SuperSimplex};
+
+// 기후 노이즈의 크기 - 바이옴 하나가 대략 수백 블록에 걸친다
+const CLIMATE_SCALE: f64 = 512.0;
+// 클수록 바이옴 경계에서 지형이 더 넓게 섞인다
+const BLEND_WIDTH: f64 = 0.02;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Biome {
+    Plains,
+    Forest,
+    Desert,
+    Tundra,
+}
+
+pub struct BiomeSettings {
+    pub name: &'static str,
+    pub surface: BlockID,
+    pub subsurface: BlockID,
+    // 표면 아래에 subsurface 블록이 몇 칸 깔리는지
+    pub subsurface_depth: i32,
+    pub base_height: f64,
+    pub height_scale: f64,
+    // 기둥(x, z) 하나에 나무가 자랄 확률 (꽃 같은 다른 식물도 이 값에 비례한다)
+    pub vegetation_density: f64,
+    // 바이옴의 (기온, 습도) 위치 - 둘 다 -1..1
+    pub climate: (f64, f64),
+}
+
+lazy_static! {
+    static ref BIOME_SETTINGS: [BiomeSettings; 4] = [
+        BiomeSettings {
+            name: "plains",
+            surface: BLOCKS.id("grass_block"),
+            subsurface: BLOCKS.id("dirt"),
+            subsurface_depth: 2,
+            base_height: 20.0,
+            height_scale: 6.0,
+            vegetation_density: 0.002,
+            climate: (0.2, 0.0),
+        },
+        BiomeSettings {
+            name: "forest",
+            surface: BLOCKS.id("grass_block"),
+            subsurface: BLOCKS.id("dirt"),
+            subsurface_depth: 3,
+            base_height: 22.0,
+            height_scale: 12.0,
+            vegetation_density: 0.03,
+            climate: (0.1, 0.6),
+        },
+        BiomeSettings {
+            name: "desert",
+            surface: BLOCKS.id("sand"),
+            subsurface: BLOCKS.id("sandstone"),
+            subsurface_depth: 4,
+            base_height: 18.0,
+            height_scale: 4.0,
+            vegetation_density: 0.0,
+            climate: (0.6, -0.5),
+        },
+        BiomeSettings {
+            name: "tundra",
+            surface: BLOCKS.id("snowy_grass_block"),
+            subsurface: BLOCKS.id("dirt"),
+            subsurface_depth: 2,
+            base_height: 24.0,
+            height_scale: 20.0,
+            vegetation_density: 0.004,
+            climate: (-0.6, 0.0),
+        },
+    ];
+}
+
+impl Biome {
+    pub const ALL: [Biome; 4] = [Biome::Plains, Biome::Forest, Biome::Desert, Biome::Tundra];
+
+    pub fn settings(&self) -> &'static BiomeSettings {
+        &BIOME_SETTINGS[*self as usize]
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.settings().name
+    }
+}
+
+// 온도와 습도 노이즈로 위치마다 바이옴을 정한다
+pub struct BiomeMap {
+    temperature: SuperSimplex,
+    humidity: SuperSimplex,
+}
+
+impl BiomeMap {
+    pub fn new(temperature_seed: u32, humidity_seed: u32) -> Self {
+        Self {
+            temperature: SuperSimplex::new(temperature_seed),
+            humidity: SuperSimplex::new(humidity_seed),
+        }
+    }
+
+    pub fn climate_at(&self, x: i32, z: i32) -> (f64, f64) {
+        let point = [x as f64 / CLIMATE_SCALE, z as f64 / CLIMATE_SCALE];
+
+        (self.temperature.get(point), self.humidity.get(point))
+    }
+
+    fn climate_distance(biome: Biome, (temperature, humidity): (f64, f64)) -> f64 {
+        let (t, h) = biome.settings().climate;
+        (t - temperature).powi(2) + (h - humidity).powi(2)
+    }
+
+    // 기후가 가장 가까운 바이옴
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome {
+        let climate = self.climate_at(x, z);
+
+        Biome::ALL
+            .into_iter()
+            .min_by(|&a, &b| {
+                BiomeMap::climate_distance(a, climate)
+                    .total_cmp(&BiomeMap::climate_distance(b, climate))
+            })
+            .unwrap()
+    }
+
+    // 위치마다 각 바이옴이 섞이는 비율 (합은 1)
+    pub fn weights_at(&self, x: i32, z: i32) -> [(Biome, f64); 4] {
+        let climate = self.climate_at(x, z);
+        let distances = Biome::ALL.map(|biome| BiomeMap::climate_distance(biome, climate));
+        let nearest = distances.iter().copied().fold(f64::INFINITY, f64::min);
+
+        let mut weights = [(Biome::Plains, 0.0); 4];
+        let mut total = 0.0;
+        for (i, biome) in Biome::ALL.into_iter().enumerate() {
+            let weight = (-(distances[i] - nearest) / BLEND_WIDTH).exp();
+            weights[i] = (biome, weight);
+            total += weight;
+        }
+
+        for (_, weight) in weights.iter_mut() {
+            *weight /= total;
+        }
+
+        weights
+    }
+}
@@ -0,0 +1,437 @@
+use crate::block_state::{BlockState, StateProperty};
+use crate::block_texture_sides::BlockFaces;
+use crate::block_ticks::RandomTick;
+use crate::chunk::BlockID;
+use crate::constants::BLOCK_REGISTRY_PATH;
+use crate::mesher::RenderLayer;
+use crate::voxel_shape::{VoxelShape, EMPTY_SHAPE, FULL_SHAPE};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+fn default_solid() -> bool {
+    true
+}
+
+// 유체 블록의 흐름 설정 (blocks.json의 "fluid")
+#[derive(Copy, Clone, Debug, Deserialize)]
+pub struct FluidProperties {
+    // 원천에서 옆으로 몇 칸까지 흐르는지
+    pub flow_distance: u8,
+    // 흐름 한 단계마다 기다리는 월드 틱 수
+    pub tick_delay: u32,
+}
+
+// 터지는 블록의 설정 (blocks.json의 "explosive")
+#[derive(Copy, Clone, Debug, Deserialize)]
+pub struct ExplosiveProperties {
+    // 폭발의 세기 - 대략 몇 칸까지 부수는지
+    pub power: f32,
+    // 불을 붙이고 터지기까지 걸리는 시간 (초)
+    pub fuse: f32,
+}
+
+// 유체 블록 하나의 상태 - level 0은 원천, 1부터는 원천에서 떨어진 거리
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct FluidState {
+    pub source: BlockID,
+    pub level: u8,
+    // 위에서 떨어지는 중인 유체
+    pub falling: bool,
+}
+
+impl FluidState {
+    pub fn is_source(&self) -> bool {
+        self.level == 0 && !self.falling
+    }
+}
+
+// 블록 한 종류에 대한 정의 (blocks/blocks.json의 항목 하나)
+#[derive(Clone, Debug, Deserialize)]
+pub struct BlockDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub faces: Option<BlockFaces<String>>,
+    #[serde(default)]
+    pub transparent: bool,
+    #[serde(default = "default_solid")]
+    pub solid: bool,
+    #[serde(default)]
+    pub hardness: f32,
+    #[serde(default)]
+    pub light_emission: u8,
+    #[serde(default)]
+    pub fluid: Option<FluidProperties>,
+    #[serde(default)]
+    pub random_tick: Option<RandomTick>,
+    // 근처의 잎이 사라지지 않게 붙잡아 두는 블록 (통나무)
+    #[serde(default)]
+    pub sustains_leaves: bool,
+    // 아래가 비면 떨어지는 블록 (모래, 자갈)
+    #[serde(default)]
+    pub gravity: bool,
+    // 폭발을 견디는 정도 - 없으면 단단함과 같다
+    #[serde(default)]
+    pub blast_resistance: Option<f32>,
+    // 불을 붙이면 터지는 블록 (TNT)
+    #[serde(default)]
+    pub explosive: Option<ExplosiveProperties>,
+    // 그려지는 모양 - 없으면 꽉 찬 블록. 충돌과 조준 모양의 기본값이기도 하다
+    #[serde(default)]
+    pub shape: Option<VoxelShape>,
+    // 부딪히는 모양 - 없으면 고체는 shape, 아니면 비어 있다
+    #[serde(default)]
+    pub collision_shape: Option<VoxelShape>,
+    // 조준했을 때 잡히는 모양 - 없으면 공기와 유체 말고는 shape
+    #[serde(default)]
+    pub selection_shape: Option<VoxelShape>,
+    // 그리는 단계 - 없으면 투명한 블록은 cutout, 아니면 opaque
+    #[serde(default)]
+    pub render_layer: Option<RenderLayer>,
+    // 상태의 종류 - 상태마다 블록이 하나씩 등록된다
+    #[serde(default)]
+    pub state: Option<StateProperty>,
+    // 레지스트리가 원천 블록과 흐르는 단계마다 채운다
+    #[serde(skip)]
+    pub fluid_state: Option<FluidState>,
+    // 레지스트리가 "state"가 있는 블록의 상태마다 채운다
+    #[serde(skip)]
+    pub block_state: Option<BlockState>,
+}
+
+impl BlockDefinition {
+    pub fn blast_resistance(&self) -> f32 {
+        self.blast_resistance.unwrap_or(self.hardness)
+    }
+
+    pub fn render_layer(&self) -> RenderLayer {
+        match self.render_layer {
+            Some(layer) => layer,
+            None if self.transparent => RenderLayer::Cutout,
+            None => RenderLayer::Opaque,
+        }
+    }
+
+    pub fn shape(&self) -> &VoxelShape {
+        self.shape.as_ref().unwrap_or(&FULL_SHAPE)
+    }
+
+    pub fn collision_shape(&self) -> &VoxelShape {
+        match &self.collision_shape {
+            Some(shape) => shape,
+            None if self.solid => self.shape(),
+            None => &EMPTY_SHAPE,
+        }
+    }
+
+    pub fn selection_shape(&self) -> &VoxelShape {
+        match &self.selection_shape {
+            Some(shape) => shape,
+            None if self.name == "air" || self.fluid.is_some() => &EMPTY_SHAPE,
+            None => self.shape(),
+        }
+    }
+
+    fn air() -> Self {
+        Self {
+            name: "air".to_owned(),
+            faces: None,
+            transparent: true,
+            solid: false,
+            hardness: 0.0,
+            light_emission: 0,
+            fluid: None,
+            random_tick: None,
+            sustains_leaves: false,
+            gravity: false,
+            blast_resistance: None,
+            explosive: None,
+            shape: None,
+            collision_shape: None,
+            selection_shape: None,
+            render_layer: None,
+            state: None,
+            fluid_state: None,
+            block_state: None,
+        }
+    }
+}
+
+pub struct BlockRegistry {
+    blocks: Vec<BlockDefinition>,
+    ids: HashMap<String, BlockID>,
+    fluid_variants: HashMap<FluidState, BlockID>,
+    state_variants: HashMap<BlockState, BlockID>,
+}
+
+impl BlockRegistry {
+    // 공기는 항상 처음에 등록해서 어느 데이터 파일에서나 BlockID::AIR (0)가 공기가 되게 한다
+    pub fn from_definitions(definitions: Vec<BlockDefinition>) -> Result<BlockRegistry, String> {
+        let mut registry = BlockRegistry {
+            blocks: Vec::new(),
+            ids: HashMap::new(),
+            fluid_variants: HashMap::new(),
+            state_variants: HashMap::new(),
+        };
+
+        registry.register(BlockDefinition::air())?;
+        for definition in definitions {
+            match (definition.fluid, definition.state) {
+                (Some(_), Some(_)) => {
+                    return Err(format!("Fluid '{}' can't have a state", definition.name))
+                }
+                (Some(fluid), None) => registry.register_fluid(definition, fluid)?,
+                (None, Some(property)) => registry.register_states(definition, property)?,
+                (None, None) => {
+                    registry.register(definition)?;
+                }
+            }
+        }
+
+        Ok(registry)
+    }
+
+    pub fn from_json(source: &str) -> Result<BlockRegistry, String> {
+        let definitions: Vec<BlockDefinition> =
+            serde_json::from_str(source).map_err(|err| err.to_string())?;
+        BlockRegistry::from_definitions(definitions)
+    }
+
+    pub fn load(path: &str) -> Result<BlockRegistry, String> {
+        let source = std::fs::read_to_string(path).map_err(|err| format!("{path}: {err}"))?;
+        BlockRegistry::from_json(&source).map_err(|err| format!("{path}: {err}"))
+    }
+
+    fn register(&mut self, definition: BlockDefinition) -> Result<BlockID, String> {
+        if self.ids.contains_key(&definition.name) {
+            return Err(format!("Block '{}' is registered twice", definition.name));
+        }
+        if self.blocks.len() > u16::MAX as usize {
+            return Err("Too many blocks".to_owned());
+        }
+        if definition.light_emission > 15 {
+            return Err(format!(
+                "Block '{}' has light emission {} (max 15)",
+                definition.name, definition.light_emission
+            ));
+        }
+
+        for shape in [
+            &definition.shape,
+            &definition.collision_shape,
+            &definition.selection_shape,
+        ] {
+            if let Some(shape) = shape {
+                shape
+                    .validate()
+                    .map_err(|err| format!("Block '{}': {err}", definition.name))?;
+            }
+        }
+
+        let id = BlockID(self.blocks.len() as u16);
+        self.ids.insert(definition.name.clone(), id);
+        self.blocks.push(definition);
+
+        Ok(id)
+    }
+
+    // 유체는 원천 블록 외에 흐르는 단계마다 블록을 하나씩 등록한다 ("water_flowing_1"..)
+    fn register_fluid(
+        &mut self,
+        definition: BlockDefinition,
+        fluid: FluidProperties,
+    ) -> Result<(), String> {
+        if fluid.flow_distance == 0 || fluid.flow_distance > 15 {
+            return Err(format!(
+                "Fluid '{}' has flow distance {} (1 ~ 15)",
+                definition.name, fluid.flow_distance
+            ));
+        }
+
+        let source = BlockID(self.blocks.len() as u16);
+        let mut variants = vec![(definition.name.clone(), 0, false)];
+        for level in 1..=fluid.flow_distance {
+            variants.push((format!("{}_flowing_{level}", definition.name), level, false));
+        }
+        variants.push((format!("{}_falling", definition.name), 0, true));
+
+        for (name, level, falling) in variants {
+            let state = FluidState {
+                source,
+                level,
+                falling,
+            };
+            // 유체는 항상 통과할 수 있다
+            let id = self.register(BlockDefinition {
+                name,
+                solid: false,
+                fluid_state: Some(state),
+                ..definition.clone()
+            })?;
+            self.fluid_variants.insert(state, id);
+        }
+
+        Ok(())
+    }
+
+    // 상태마다 블록을 하나씩 등록한다 - 기본 상태는 원래 이름, 나머지는 "oak_log_axis_x"처럼
+    fn register_states(
+        &mut self,
+        definition: BlockDefinition,
+        property: StateProperty,
+    ) -> Result<(), String> {
+        if let StateProperty::Level(max) = property {
+            if max == 0 {
+                return Err(format!(
+                    "Block '{}' has a level state with no levels",
+                    definition.name
+                ));
+            }
+        }
+
+        let base = BlockID(self.blocks.len() as u16);
+        for (i, value) in property.values().into_iter().enumerate() {
+            let name = if i == 0 {
+                definition.name.clone()
+            } else {
+                format!("{}_{}", definition.name, value.suffix())
+            };
+            let transform = |shape: &Option<VoxelShape>| {
+                shape
+                    .as_ref()
+                    .map(|shape| value.transform_shape(shape, property))
+            };
+
+            let state = BlockState { base, value };
+            let id = self.register(BlockDefinition {
+                name,
+                shape: transform(&definition.shape),
+                collision_shape: transform(&definition.collision_shape),
+                selection_shape: transform(&definition.selection_shape),
+                block_state: Some(state),
+                ..definition.clone()
+            })?;
+            self.state_variants.insert(state, id);
+        }
+
+        Ok(())
+    }
+
+    pub fn state_block(&self, state: BlockState) -> Option<BlockID> {
+        self.state_variants.get(&state).copied()
+    }
+
+    pub fn fluid_block(&self, state: FluidState) -> Option<BlockID> {
+        self.fluid_variants.get(&state).copied()
+    }
+
+    #[inline]
+    pub fn get(&self, id: BlockID) -> &BlockDefinition {
+        &self.blocks[id.0 as usize]
+    }
+
+    pub fn get_id(&self, name: &str) -> Option<BlockID> {
+        self.ids.get(name).copied()
+    }
+
+    // 생성 코드처럼 반드시 있어야 하는 블록을 찾을 때 사용
+    pub fn id(&self, name: &str) -> BlockID {
+        self.get_id(name)
+            .unwrap_or_else(|| panic!("Block '{name}' is not in the block registry"))
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (BlockID, &BlockDefinition)> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .map(|(i, definition)| (BlockID(i as u16), definition))
+    }
+}
+
+lazy_static! {
+    pub static ref BLOCKS: BlockRegistry =
+        BlockRegistry::load(BLOCK_REGISTRY_PATH).unwrap_or_else(|err| panic!("{err}"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL_REGISTRY: &str = r#"[
+        { "name": "stone", "faces": { "all": "blocks/stone.png" }, "hardness": 1.5 },
+        { "name": "glass", "faces": { "all": "blocks/glass.png" }, "transparent": true },
+        { "name": "slab", "faces": { "all": "blocks/stone.png" }, "shape": [[0, 0, 0, 1, 0.5, 1]] },
+        { "name": "lamp", "faces": { "all": "blocks/lamp.png" }, "light_emission": 15 }
+    ]"#;
+
+    #[test]
+    fn small_json_file_is_loaded_after_air() {
+        let path = std::env::temp_dir().join(format!("blocks-{}.json", std::process::id()));
+        std::fs::write(&path, SMALL_REGISTRY).unwrap();
+        let registry = BlockRegistry::load(path.to_str().unwrap()).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(registry.len(), 5);
+        assert_eq!(registry.get(BlockID::AIR).name, "air");
+        assert_eq!(registry.get(registry.id("stone")).hardness, 1.5);
+        assert!(registry.get(registry.id("glass")).transparent);
+        assert_eq!(registry.get(registry.id("lamp")).light_emission, 15);
+        let slab = registry.get(registry.id("slab")).shape();
+        assert_eq!(slab.boxes.len(), 1);
+        assert_eq!(slab.boxes[0].maxs.y, 0.5);
+    }
+
+    #[test]
+    fn ids_follow_the_file_order() {
+        let registry = BlockRegistry::from_json(SMALL_REGISTRY).unwrap();
+
+        assert_eq!(registry.get_id("air"), Some(BlockID::AIR));
+        assert_eq!(registry.get_id("stone"), Some(BlockID(1)));
+        assert_eq!(registry.get_id("lamp"), Some(BlockID(4)));
+        assert_eq!(registry.id("glass"), BlockID(2));
+        for (id, definition) in registry.iter() {
+            assert_eq!(registry.id(&definition.name), id);
+        }
+    }
+
+    #[test]
+    fn unknown_names_are_rejected() {
+        let registry = BlockRegistry::from_json(SMALL_REGISTRY).unwrap();
+        assert_eq!(registry.get_id("dirt"), None);
+        assert!(std::panic::catch_unwind(|| registry.id("dirt")).is_err());
+
+        let unknown_tick = r#"[{ "name": "moss", "random_tick": "grow_moss" }]"#;
+        assert!(BlockRegistry::from_json(unknown_tick).is_err());
+    }
+
+    #[test]
+    fn duplicate_names_are_rejected() {
+        let twice = r#"[{ "name": "stone" }, { "name": "stone" }]"#;
+        let err = BlockRegistry::from_json(twice).err().unwrap();
+        assert!(err.contains("'stone' is registered twice"), "{err}");
+
+        let air = r#"[{ "name": "air" }]"#;
+        assert!(BlockRegistry::from_json(air).is_err());
+    }
+
+    #[test]
+    fn invalid_shapes_are_rejected() {
+        let flat = r#"[{ "name": "carpet", "shape": [[0, 0, 0, 1, 0, 1]] }]"#;
+        let err = BlockRegistry::from_json(flat).err().unwrap();
+        assert!(err.starts_with("Block 'carpet'"), "{err}");
+
+        let short = r#"[{ "name": "carpet", "shape": [[0, 0, 0, 1, 0.1]] }]"#;
+        assert!(BlockRegistry::from_json(short).is_err());
+        let bright = r#"[{ "name": "sun", "light_emission": 16 }]"#;
+        assert!(BlockRegistry::from_json(bright).is_err());
+    }
+
+    #[test]
+    fn broken_files_report_their_path() {
+        let err = BlockRegistry::load("blocks/missing.json").err().unwrap();
+        assert!(err.starts_with("blocks/missing.json: "), "{err}");
+    }
+}
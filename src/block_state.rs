@@ -0,0 +1,213 @@
+use crate::block_registry::BLOCKS;
+use crate::chunk::BlockID;
+use crate::voxel_shape::VoxelShape;
+use nalgebra_glm::{vec3, IVec3, Vec3};
+use serde::Deserialize;
+
+// 블록이 가질 수 있는 상태의 종류 (blocks.json의 "state")
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StateProperty {
+    // 통나무처럼 놓인 축
+    Axis,
+    // 계단처럼 앞면이 바라보는 수평 방향
+    Facing,
+    // 반 블록이 칸의 아래쪽과 위쪽 중 어디에 붙었는지
+    Half,
+    // 쌓인 눈처럼 단계가 있는 블록 - 0부터 주어진 최댓값까지
+    Level(u8),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Facing {
+    North,
+    South,
+    East,
+    West,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Half {
+    Bottom,
+    Top,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum StateValue {
+    Axis(Axis),
+    Facing(Facing),
+    Half(Half),
+    Level(u8),
+}
+
+// 상태가 있는 블록의 한 가지 상태 - 같은 블록의 다른 상태는 base가 같다
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct BlockState {
+    pub base: BlockID,
+    pub value: StateValue,
+}
+
+// 축에 맞춘 90도 단위 회전 (블록 가운데를 중심으로 돌린다)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Rotation([[i32; 3]; 3]);
+
+impl Rotation {
+    pub const IDENTITY: Rotation = Rotation([[1, 0, 0], [0, 1, 0], [0, 0, 1]]);
+
+    pub fn apply(&self, v: [i32; 3]) -> [i32; 3] {
+        let m = &self.0;
+        [0, 1, 2].map(|row| m[row][0] * v[0] + m[row][1] * v[1] + m[row][2] * v[2])
+    }
+
+    // 블록 안의 점 (0..1)을 돌린다
+    pub fn apply_point(&self, p: [f32; 3]) -> [f32; 3] {
+        let m = &self.0;
+        let centered = [p[0] - 0.5, p[1] - 0.5, p[2] - 0.5];
+        [0, 1, 2].map(|row| {
+            m[row][0] as f32 * centered[0]
+                + m[row][1] as f32 * centered[1]
+                + m[row][2] as f32 * centered[2]
+                + 0.5
+        })
+    }
+
+    // 회전 행렬의 역행렬은 전치 행렬이다
+    pub fn inverse(&self) -> Rotation {
+        let m = &self.0;
+        Rotation([0, 1, 2].map(|row| [m[0][row], m[1][row], m[2][row]]))
+    }
+}
+
+impl StateProperty {
+    // 첫 번째 값이 기본 상태 (이름에 접미사가 붙지 않는 블록)
+    pub fn values(&self) -> Vec<StateValue> {
+        match *self {
+            StateProperty::Axis => [Axis::Y, Axis::X, Axis::Z].map(StateValue::Axis).to_vec(),
+            StateProperty::Facing => [Facing::South, Facing::North, Facing::East, Facing::West]
+                .map(StateValue::Facing)
+                .to_vec(),
+            StateProperty::Half => [Half::Bottom, Half::Top].map(StateValue::Half).to_vec(),
+            StateProperty::Level(max) => (0..=max).map(StateValue::Level).collect(),
+        }
+    }
+}
+
+impl StateValue {
+    // 상태별로 등록되는 블록 이름의 접미사 ("oak_log_axis_x")
+    pub fn suffix(&self) -> String {
+        match self {
+            StateValue::Axis(axis) => format!("axis_{}", format!("{axis:?}").to_lowercase()),
+            StateValue::Facing(facing) => {
+                format!("facing_{}", format!("{facing:?}").to_lowercase())
+            }
+            StateValue::Half(half) => format!("half_{}", format!("{half:?}").to_lowercase()),
+            StateValue::Level(level) => format!("level_{level}"),
+        }
+    }
+
+    // 모델(텍스쳐와 모양)을 돌리는 회전 - 모델의 위는 +y, 앞은 +z
+    pub fn rotation(&self) -> Rotation {
+        match self {
+            StateValue::Axis(Axis::Y) => Rotation::IDENTITY,
+            // +y를 +x로
+            StateValue::Axis(Axis::X) => Rotation([[0, 1, 0], [-1, 0, 0], [0, 0, 1]]),
+            // +y를 +z로
+            StateValue::Axis(Axis::Z) => Rotation([[1, 0, 0], [0, 0, -1], [0, 1, 0]]),
+            StateValue::Facing(Facing::South) => Rotation::IDENTITY,
+            StateValue::Facing(Facing::North) => Rotation([[-1, 0, 0], [0, 1, 0], [0, 0, -1]]),
+            StateValue::Facing(Facing::East) => Rotation([[0, 0, 1], [0, 1, 0], [-1, 0, 0]]),
+            StateValue::Facing(Facing::West) => Rotation([[0, 0, -1], [0, 1, 0], [1, 0, 0]]),
+            // 위쪽 반 블록과 단계는 텍스쳐를 돌리지 않고 모양만 바꾼다
+            StateValue::Half(_) | StateValue::Level(_) => Rotation::IDENTITY,
+        }
+    }
+
+    // 기본 상태의 모양을 이 상태의 모양으로 바꾼다
+    pub fn transform_shape(&self, shape: &VoxelShape, property: StateProperty) -> VoxelShape {
+        match (self, property) {
+            // 위아래를 뒤집는다
+            (StateValue::Half(Half::Top), _) => shape.transformed(|p| vec3(p.x, 1.0 - p.y, p.z)),
+            // 한 단계마다 높이가 1 / (max + 1)씩 높아진다
+            (StateValue::Level(level), StateProperty::Level(max)) => {
+                let scale = (*level as f32 + 1.0) / (max as f32 + 1.0);
+                shape.transformed(|p| vec3(p.x, p.y * scale, p.z))
+            }
+            _ => {
+                let rotation = self.rotation();
+                shape.transformed(|p| {
+                    let [x, y, z] = rotation.apply_point([p.x, p.y, p.z]);
+                    vec3(x, y, z)
+                })
+            }
+        }
+    }
+}
+
+// 가장 많이 향하는 수평 방향
+fn horizontal_facing(direction: &Vec3) -> Facing {
+    if direction.x.abs() > direction.z.abs() {
+        if direction.x > 0.0 {
+            Facing::East
+        } else {
+            Facing::West
+        }
+    } else if direction.z > 0.0 {
+        Facing::South
+    } else {
+        Facing::North
+    }
+}
+
+// 블록을 놓을 때의 상태 - 맞은 면의 방향(normal), 맞은 점의 높이, 플레이어가 보는 방향으로 정한다
+pub fn placement_state(block: BlockID, normal: &IVec3, hit: &Vec3, look: &Vec3) -> BlockID {
+    let base = match block.definition().block_state {
+        Some(state) => state.base,
+        None => return block,
+    };
+    let property = match base.definition().state {
+        Some(property) => property,
+        None => return block,
+    };
+
+    let value = match property {
+        StateProperty::Axis => StateValue::Axis(match (normal.x, normal.y, normal.z) {
+            (x, _, _) if x != 0 => Axis::X,
+            (_, _, z) if z != 0 => Axis::Z,
+            _ => Axis::Y,
+        }),
+        StateProperty::Facing => StateValue::Facing(horizontal_facing(&-look)),
+        StateProperty::Half => StateValue::Half(match normal.y {
+            -1 => Half::Top,
+            1 => Half::Bottom,
+            _ if hit.y - hit.y.floor() > 0.5 => Half::Top,
+            _ => Half::Bottom,
+        }),
+        StateProperty::Level(_) => StateValue::Level(0),
+    };
+
+    BLOCKS
+        .state_block(BlockState { base, value })
+        .unwrap_or(block)
+}
+
+// 같은 블록을 단계가 있는 블록 위에 놓으면 새로 놓는 대신 한 단계 쌓는다 (눈)
+pub fn stacked_level(target: BlockID, placed: BlockID) -> Option<BlockID> {
+    let state = target.definition().block_state?;
+    let placed_base = placed.definition().block_state?.base;
+    let level = match state.value {
+        StateValue::Level(level) if state.base == placed_base => level,
+        _ => return None,
+    };
+
+    BLOCKS.state_block(BlockState {
+        base: state.base,
+        value: StateValue::Level(level.checked_add(1)?),
+    })
+}
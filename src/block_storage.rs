@@ -0,0 +1,314 @@
+use crate::chunk::BlockID;
+use crate::chunk_manager::CHUNK_VOLUME;
+
+// 청크 안의 블록 4096개를 팔레트 + 가변 비트 인덱스로 저장한다 (한 종류뿐이면 그 블록만)
+#[derive(Clone, Debug)]
+pub enum BlockStorage {
+    Single(BlockID),
+    Paletted(PalettedBlocks),
+}
+
+#[derive(Clone, Debug)]
+pub struct PalettedBlocks {
+    palette: Vec<BlockID>,
+    bits: u32,
+    // 인덱스는 u64 두 개에 걸치지 않는다 (u64 하나에 64 / bits개)
+    data: Vec<u64>,
+}
+
+fn words_for(bits: u32) -> usize {
+    let per_word = (64 / bits) as usize;
+    (CHUNK_VOLUME as usize + per_word - 1) / per_word
+}
+
+// 팔레트 크기에 필요한 비트 수 (최소 1)
+fn bits_for(palette_len: usize) -> u32 {
+    (usize::BITS - (palette_len.max(2) - 1).leading_zeros()).max(1)
+}
+
+impl PalettedBlocks {
+    fn with_palette(palette: Vec<BlockID>) -> Self {
+        let bits = bits_for(palette.len());
+
+        Self {
+            palette,
+            bits,
+            data: vec![0; words_for(bits)],
+        }
+    }
+
+    #[inline]
+    fn get_index(&self, i: usize) -> usize {
+        let per_word = (64 / self.bits) as usize;
+        let shift = (i % per_word) as u32 * self.bits;
+        let mask = (1u64 << self.bits) - 1;
+
+        ((self.data[i / per_word] >> shift) & mask) as usize
+    }
+
+    #[inline]
+    fn set_index(&mut self, i: usize, value: usize) {
+        let per_word = (64 / self.bits) as usize;
+        let shift = (i % per_word) as u32 * self.bits;
+        let mask = (1u64 << self.bits) - 1;
+        let word = &mut self.data[i / per_word];
+
+        *word = (*word & !(mask << shift)) | ((value as u64 & mask) << shift);
+    }
+
+    pub fn palette(&self) -> &[BlockID] {
+        &self.palette
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    pub fn data(&self) -> &[u64] {
+        &self.data
+    }
+
+    // 디스크에서 읽은 팔레트와 데이터로 다시 만든다 (형식이 맞지 않으면 None)
+    pub fn from_raw(palette: Vec<BlockID>, bits: u32, data: Vec<u64>) -> Option<Self> {
+        if bits == 0 || bits > 16 || palette.is_empty() || palette.len() > 1 << bits {
+            return None;
+        }
+        if data.len() != words_for(bits) {
+            return None;
+        }
+
+        let blocks = Self {
+            palette,
+            bits,
+            data,
+        };
+        let in_range =
+            (0..CHUNK_VOLUME as usize).all(|i| blocks.get_index(i) < blocks.palette.len());
+
+        in_range.then_some(blocks)
+    }
+
+    // 쓰이지 않는 팔레트 항목을 버리고 인덱스를 가장 적은 비트로 다시 담는다
+    fn repack(&self, extra_entries: usize) -> BlockStorage {
+        let mut used = vec![false; self.palette.len()];
+        for i in 0..CHUNK_VOLUME as usize {
+            used[self.get_index(i)] = true;
+        }
+
+        let mut remap = vec![0; self.palette.len()];
+        let mut palette = Vec::new();
+        for (old, &block) in self.palette.iter().enumerate() {
+            if used[old] {
+                remap[old] = palette.len();
+                palette.push(block);
+            }
+        }
+
+        if palette.len() == 1 && extra_entries == 0 {
+            return BlockStorage::Single(palette[0]);
+        }
+
+        let mut repacked = PalettedBlocks::with_palette(palette);
+        repacked.bits = bits_for(repacked.palette.len() + extra_entries);
+        repacked.data = vec![0; words_for(repacked.bits)];
+
+        for i in 0..CHUNK_VOLUME as usize {
+            repacked.set_index(i, remap[self.get_index(i)]);
+        }
+
+        BlockStorage::Paletted(repacked)
+    }
+}
+
+impl BlockStorage {
+    pub fn from_blocks(blocks: &[BlockID]) -> Self {
+        let mut storage = BlockStorage::Single(blocks[0]);
+        for (i, &block) in blocks.iter().enumerate() {
+            storage.set(i, block);
+        }
+
+        storage
+    }
+
+    #[inline]
+    pub fn get(&self, i: usize) -> BlockID {
+        match self {
+            BlockStorage::Single(block) => *block,
+            BlockStorage::Paletted(blocks) => blocks.palette[blocks.get_index(i)],
+        }
+    }
+
+    pub fn set(&mut self, i: usize, block: BlockID) {
+        match self {
+            BlockStorage::Single(current) => {
+                if *current == block {
+                    return;
+                }
+
+                let mut blocks = PalettedBlocks::with_palette(vec![*current, block]);
+                blocks.set_index(i, 1);
+                *self = BlockStorage::Paletted(blocks);
+            }
+            BlockStorage::Paletted(blocks) => {
+                if let Some(index) = blocks.palette.iter().position(|&b| b == block) {
+                    blocks.set_index(i, index);
+                    return;
+                }
+
+                if blocks.palette.len() < 1 << blocks.bits {
+                    blocks.palette.push(block);
+                    blocks.set_index(i, blocks.palette.len() - 1);
+                    return;
+                }
+
+                // 팔레트가 가득 참 - 안 쓰는 항목을 정리하고 필요하면 비트 수를 늘린다
+                *self = blocks.repack(1);
+                self.set(i, block);
+            }
+        }
+    }
+
+    // 한 종류의 블록만 남았다면 Single로 되돌린다
+    pub fn optimize(&mut self) {
+        if let BlockStorage::Paletted(blocks) = self {
+            *self = blocks.repack(0);
+        }
+    }
+
+    pub fn is_uniform(&self) -> Option<BlockID> {
+        match self {
+            BlockStorage::Single(block) => Some(*block),
+            BlockStorage::Paletted(_) => None,
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<BlockID> {
+        (0..CHUNK_VOLUME as usize).map(|i| self.get(i)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    const VOLUME: usize = CHUNK_VOLUME as usize;
+
+    fn paletted(storage: &BlockStorage) -> &PalettedBlocks {
+        match storage {
+            BlockStorage::Paletted(blocks) => blocks,
+            BlockStorage::Single(block) => panic!("Expected a palette, found only {block:?}"),
+        }
+    }
+
+    fn assert_matches(storage: &BlockStorage, expected: &[BlockID; VOLUME]) {
+        for (i, &block) in expected.iter().enumerate() {
+            assert_eq!(storage.get(i), block, "Block {i}");
+        }
+    }
+
+    #[test]
+    fn single_block_stays_single_until_another_block_is_set() {
+        let mut storage = BlockStorage::Single(BlockID(3));
+        assert!((0..VOLUME).all(|i| storage.get(i) == BlockID(3)));
+
+        storage.set(100, BlockID(3));
+        assert_eq!(storage.is_uniform(), Some(BlockID(3)));
+
+        storage.set(100, BlockID(7));
+        assert_eq!(storage.is_uniform(), None);
+        assert_eq!(paletted(&storage).bits(), 1);
+        assert_eq!(storage.get(100), BlockID(7));
+        assert_eq!(storage.get(99), BlockID(3));
+        assert_eq!(storage.get(101), BlockID(3));
+    }
+
+    #[test]
+    fn palette_grows_across_every_bit_width() {
+        let mut storage = BlockStorage::Single(BlockID(0));
+        let mut expected = [BlockID(0); VOLUME];
+
+        // 블록 종류가 2, 3, 5, 9, 17, ..., 257개가 될 때 비트 수가 늘어난다
+        for kinds in 2..=300usize {
+            let block = BlockID(kinds as u16 - 1);
+            let i = (kinds * 13) % VOLUME;
+            storage.set(i, block);
+            expected[i] = block;
+
+            let bits = (usize::BITS - (kinds - 1).leading_zeros()).max(1);
+            assert_eq!(paletted(&storage).bits(), bits, "{kinds} kinds");
+            assert_eq!(paletted(&storage).palette().len(), kinds);
+            assert_matches(&storage, &expected);
+        }
+    }
+
+    #[test]
+    fn full_palette_drops_unused_entries_before_growing() {
+        let mut storage = BlockStorage::Single(BlockID(1));
+        storage.set(0, BlockID(2));
+        // BlockID(1)은 팔레트에 남아 있지만 더 이상 쓰이지 않는다
+        for i in 0..VOLUME {
+            storage.set(i, BlockID(2));
+        }
+        assert_eq!(paletted(&storage).palette(), &[BlockID(1), BlockID(2)]);
+
+        storage.set(5, BlockID(3));
+        assert_eq!(paletted(&storage).bits(), 1);
+        assert_eq!(paletted(&storage).palette(), &[BlockID(2), BlockID(3)]);
+        assert_eq!(storage.get(5), BlockID(3));
+        assert!((0..VOLUME)
+            .filter(|&i| i != 5)
+            .all(|i| storage.get(i) == BlockID(2)));
+    }
+
+    #[test]
+    fn optimize_shrinks_the_storage() {
+        let mut storage = BlockStorage::Single(BlockID(0));
+        let mut expected = [BlockID(0); VOLUME];
+        for i in 0..20 {
+            storage.set(i, BlockID(i as u16));
+            expected[i] = BlockID(i as u16);
+        }
+        assert_eq!(paletted(&storage).bits(), 5);
+
+        // 세 종류만 남기면 2비트로 줄어든다
+        for i in 3..20 {
+            storage.set(i, BlockID(0));
+            expected[i] = BlockID(0);
+        }
+        storage.optimize();
+        assert_eq!(paletted(&storage).bits(), 2);
+        assert_eq!(paletted(&storage).palette().len(), 3);
+        assert_matches(&storage, &expected);
+
+        for i in 0..3 {
+            storage.set(i, BlockID(0));
+        }
+        storage.optimize();
+        assert_eq!(storage.is_uniform(), Some(BlockID(0)));
+    }
+
+    #[test]
+    fn random_edits_match_a_plain_array() {
+        let mut rng = ChaCha8Rng::seed_from_u64(6);
+        let mut storage = BlockStorage::Single(BlockID(0));
+        let mut expected = [BlockID(0); VOLUME];
+
+        for step in 0..20_000 {
+            let i = rng.gen_range(0..VOLUME);
+            // 처음에는 종류가 적고 점점 많아지도록
+            let kinds = 2 + step / 500;
+            let block = BlockID(rng.gen_range(0..kinds) as u16);
+            storage.set(i, block);
+            expected[i] = block;
+
+            if step % 1000 == 999 {
+                storage.optimize();
+                assert_matches(&storage, &expected);
+            }
+        }
+        assert_matches(&storage, &expected);
+        assert_eq!(storage.to_vec(), expected.to_vec());
+    }
+}
@@ -1,6 +1,9 @@
-use crate::{UVCoords, UVFaces};
+use crate::texture_atlas::AtlasRegion;
+use crate::UVFaces;
+use serde::Deserialize;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum BlockFaces<T> {
     All(T),
     Sides {
@@ -18,7 +21,51 @@ pub enum BlockFaces<T> {
     },
 }
 
-pub fn get_uv_every_side(faces: BlockFaces<UVCoords>) -> UVFaces {
+impl<T> BlockFaces<T> {
+    // 정해진 값들 - All은 하나, Sides는 셋, Each는 여섯
+    pub fn values(&self) -> Vec<&T> {
+        match self {
+            BlockFaces::All(all) => vec![all],
+            BlockFaces::Sides { sides, top, bottom } => vec![sides, top, bottom],
+            BlockFaces::Each {
+                top,
+                bottom,
+                front,
+                back,
+                left,
+                right,
+            } => vec![top, bottom, front, back, left, right],
+        }
+    }
+
+    pub fn map<'a, U>(&'a self, mut f: impl FnMut(&'a T) -> U) -> BlockFaces<U> {
+        match self {
+            BlockFaces::All(all) => BlockFaces::All(f(all)),
+            BlockFaces::Sides { sides, top, bottom } => BlockFaces::Sides {
+                sides: f(sides),
+                top: f(top),
+                bottom: f(bottom),
+            },
+            BlockFaces::Each {
+                top,
+                bottom,
+                front,
+                back,
+                left,
+                right,
+            } => BlockFaces::Each {
+                top: f(top),
+                bottom: f(bottom),
+                front: f(front),
+                back: f(back),
+                left: f(left),
+                right: f(right),
+            },
+        }
+    }
+}
+
+pub fn get_uv_every_side(faces: BlockFaces<AtlasRegion>) -> UVFaces {
     match faces {
         BlockFaces::All(uv) => (uv, uv, uv, uv, uv, uv),
         BlockFaces::Sides { sides, top, bottom } => (sides, sides, top, bottom, sides, sides),
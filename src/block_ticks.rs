@@ -0,0 +1,347 @@
+use crate::block_registry::BLOCKS;
+use crate::chunk::BlockID;
+use crate::chunk_manager::{ChunkManager, CHUNK_SIZE};
+use crate::fluids;
+use crate::lighting::{self, NEIGHBOURS};
+use rand::Rng;
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
+// 잎이 이 거리(잎을 따라 걸은 칸 수) 안에 통나무가 없으면 사라진다
+const LEAF_DECAY_DISTANCE: u32 = 6;
+// 풀이 퍼지거나 살아남는 데 필요한 밝기
+const GRASS_SPREAD_LIGHT: u8 = 9;
+const GRASS_SURVIVE_LIGHT: u8 = 4;
+// 모래나 자갈이 아래가 빈 것을 알아차리고 떨어지기까지 걸리는 틱 수
+const FALLING_BLOCK_DELAY: u32 = 2;
+
+// 무작위 틱을 받았을 때 블록이 하는 일 (blocks.json의 "random_tick")
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RandomTick {
+    // 주변의 흙을 같은 풀 블록으로 바꾸고, 빛이 가려지면 흙으로 돌아간다
+    SpreadGrass,
+    // 이어진 잎을 따라가서 통나무가 없으면 사라진다
+    DecayLeaves,
+}
+
+// 청크와 함께 저장되는 예약 업데이트 - 위치는 청크 안 좌표, 시간은 남은 틱 수
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PendingTick {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+    pub delay: u32,
+}
+
+// 예약된 블록 업데이트를 틱 순서대로 꺼낸다 - 같은 위치는 한 번만 예약된다
+#[derive(Default)]
+pub struct TickScheduler {
+    pub tick: u64,
+    queue: BinaryHeap<Reverse<(u64, (i32, i32, i32))>>,
+    // 예약된 위치 -> 처리할 틱
+    scheduled: HashMap<(i32, i32, i32), u64>,
+    by_chunk: HashMap<(i32, i32, i32), HashSet<(i32, i32, i32)>>,
+}
+
+impl TickScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn chunk_of((x, y, z): (i32, i32, i32)) -> (i32, i32, i32) {
+        let (cx, cy, cz, ..) = ChunkManager::get_chunk_and_block_coords(x, y, z);
+        (cx, cy, cz)
+    }
+
+    // 이미 예약된 위치면 false
+    pub fn schedule(&mut self, position: (i32, i32, i32), delay: u32) -> bool {
+        if self.scheduled.contains_key(&position) {
+            return false;
+        }
+
+        let tick = self.tick + delay as u64;
+        self.scheduled.insert(position, tick);
+        self.by_chunk
+            .entry(TickScheduler::chunk_of(position))
+            .or_default()
+            .insert(position);
+        self.queue.push(Reverse((tick, position)));
+        true
+    }
+
+    // 지금 틱까지 처리해야 하는 업데이트 하나
+    pub fn pop_due(&mut self) -> Option<(i32, i32, i32)> {
+        while let Some(&Reverse((tick, position))) = self.queue.peek() {
+            if tick > self.tick {
+                return None;
+            }
+            self.queue.pop();
+
+            // 지워진 업데이트 (같은 위치에 다시 예약된 경우도 틱이 다르다)
+            if self.scheduled.get(&position) != Some(&tick) {
+                continue;
+            }
+            self.remove(position);
+            return Some(position);
+        }
+
+        None
+    }
+
+    fn remove(&mut self, position: (i32, i32, i32)) {
+        self.scheduled.remove(&position);
+        let chunk = TickScheduler::chunk_of(position);
+        if let Some(positions) = self.by_chunk.get_mut(&chunk) {
+            positions.remove(&position);
+            if positions.is_empty() {
+                self.by_chunk.remove(&chunk);
+            }
+        }
+    }
+
+    // 청크 하나에 예약된 업데이트 (저장용)
+    pub fn pending_in_chunk(&self, chunk: (i32, i32, i32)) -> Vec<PendingTick> {
+        let positions = match self.by_chunk.get(&chunk) {
+            Some(positions) => positions,
+            None => return Vec::new(),
+        };
+
+        positions
+            .iter()
+            .map(|&(x, y, z)| {
+                let (.., bx, by, bz) = ChunkManager::get_chunk_and_block_coords(x, y, z);
+                PendingTick {
+                    x: bx,
+                    y: by,
+                    z: bz,
+                    delay: self.scheduled[&(x, y, z)].saturating_sub(self.tick) as u32,
+                }
+            })
+            .collect()
+    }
+
+    // 청크를 내릴 때 그 청크의 업데이트를 지운다
+    pub fn remove_chunks(&mut self, chunks: &HashSet<(i32, i32, i32)>) {
+        for chunk in chunks {
+            for position in self.by_chunk.remove(chunk).unwrap_or_default() {
+                self.scheduled.remove(&position);
+            }
+        }
+    }
+
+    // 디스크에서 불러온 청크의 업데이트를 다시 예약한다
+    pub fn restore(&mut self, (cx, cy, cz): (i32, i32, i32), pending: &[PendingTick]) {
+        let size = CHUNK_SIZE as i32;
+
+        for tick in pending {
+            let position = (
+                cx * size + tick.x as i32,
+                cy * size + tick.y as i32,
+                cz * size + tick.z as i32,
+            );
+            self.schedule(position, tick.delay);
+        }
+    }
+}
+
+// 이웃이 바뀌었을 때 다시 계산해야 하는 블록이면 몇 틱 뒤에 업데이트할지
+pub fn update_delay(block: BlockID) -> Option<u32> {
+    if block.definition().gravity {
+        return Some(FALLING_BLOCK_DELAY);
+    }
+    fluids::fluid_properties(block).map(|fluid| fluid.tick_delay)
+}
+
+// 떨어지는 블록이 지나갈 수 있는 블록 (공기, 유체, 꽃 등)
+pub fn can_fall_through(block: BlockID) -> bool {
+    !block.is_solid()
+}
+
+// 떨어지던 블록이 멈췄을 때 그 자리를 차지할 수 있는지 - 아니면 아이템이 된다
+pub fn can_land_in(block: BlockID) -> bool {
+    block.is_air() || block.is_fluid()
+}
+
+// 예약된 업데이트 하나를 처리한다
+pub fn scheduled_tick(world: &mut ChunkManager, x: i32, y: i32, z: i32) {
+    let block = match world.get_block(x, y, z) {
+        Some(block) => block,
+        None => return,
+    };
+
+    if block.definition().gravity {
+        start_falling(world, x, y, z, block);
+    } else if block.is_fluid() {
+        fluids::update_fluid(world, x, y, z);
+    }
+}
+
+fn start_falling(world: &mut ChunkManager, x: i32, y: i32, z: i32, block: BlockID) {
+    if world.get_block(x, y - 1, z).map_or(false, can_fall_through) {
+        world.set_block(x, y, z, BlockID::AIR);
+        world.entities.spawn_falling_block(x, y, z, block);
+    }
+}
+
+// 청크마다 무작위로 고른 블록에 무작위 틱을 준다
+pub fn random_tick(world: &mut ChunkManager, x: i32, y: i32, z: i32, rng: &mut impl Rng) {
+    let block = match world.get_block(x, y, z) {
+        Some(block) => block,
+        None => return,
+    };
+
+    match block.definition().random_tick {
+        Some(RandomTick::SpreadGrass) => spread_grass(world, x, y, z, block, rng),
+        Some(RandomTick::DecayLeaves) => decay_leaves(world, x, y, z, block),
+        None => {}
+    }
+}
+
+fn light_above(world: &ChunkManager, x: i32, y: i32, z: i32) -> u8 {
+    world.get_light(x, y + 1, z).map_or(0, |light| {
+        lighting::sky_light(light).max(lighting::block_light(light))
+    })
+}
+
+// 위가 막혀 있지 않고 충분히 밝은 곳에서만 풀이 자란다
+fn can_grass_grow(world: &ChunkManager, x: i32, y: i32, z: i32) -> bool {
+    let above = world.get_block(x, y + 1, z);
+    above.map_or(false, |above| above.is_transparent() && !above.is_fluid())
+        && light_above(world, x, y, z) >= GRASS_SURVIVE_LIGHT
+}
+
+fn spread_grass(
+    world: &mut ChunkManager,
+    x: i32,
+    y: i32,
+    z: i32,
+    grass: BlockID,
+    rng: &mut impl Rng,
+) {
+    let dirt = BLOCKS.id("dirt");
+
+    if !can_grass_grow(world, x, y, z) {
+        world.set_block(x, y, z, dirt);
+        return;
+    }
+    if light_above(world, x, y, z) < GRASS_SPREAD_LIGHT {
+        return;
+    }
+
+    // 옆으로 한 칸, 위로 한 칸, 아래로 세 칸 안의 흙을 네 번 골라 본다
+    for _ in 0..4 {
+        let (tx, ty, tz) = (
+            x + rng.gen_range(-1..=1),
+            y + rng.gen_range(-3..=1),
+            z + rng.gen_range(-1..=1),
+        );
+
+        if world.get_block(tx, ty, tz) == Some(dirt) && can_grass_grow(world, tx, ty, tz) {
+            world.set_block(tx, ty, tz, grass);
+        }
+    }
+}
+
+fn decay_leaves(world: &mut ChunkManager, x: i32, y: i32, z: i32, leaves: BlockID) {
+    let mut queue = VecDeque::from([((x, y, z), 0)]);
+    let mut visited = HashSet::from([(x, y, z)]);
+
+    while let Some(((x, y, z), distance)) = queue.pop_front() {
+        if distance >= LEAF_DECAY_DISTANCE {
+            continue;
+        }
+
+        for (dx, dy, dz) in NEIGHBOURS {
+            let next = (x + dx, y + dy, z + dz);
+            if !visited.insert(next) {
+                continue;
+            }
+
+            match world.get_block(next.0, next.1, next.2) {
+                Some(block) if block.definition().sustains_leaves => return,
+                Some(block) if block == leaves => queue.push_back((next, distance + 1)),
+                // 불러오지 않은 곳에 통나무가 있을 수도 있으니 남겨 둔다
+                None => return,
+                _ => {}
+            }
+        }
+    }
+
+    world.set_block(x, y, z, BlockID::AIR);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut ticks: Vec<PendingTick>) -> Vec<(u32, u32, u32, u32)> {
+        ticks.sort_by_key(|tick| (tick.x, tick.y, tick.z));
+        ticks
+            .iter()
+            .map(|tick| (tick.x, tick.y, tick.z, tick.delay))
+            .collect()
+    }
+
+    #[test]
+    fn pending_ticks_are_kept_per_chunk() {
+        let mut scheduler = TickScheduler::new();
+        assert!(scheduler.schedule((1, 2, 3), 5));
+        assert!(!scheduler.schedule((1, 2, 3), 1));
+        assert!(scheduler.schedule((-1, 20, 3), 2));
+        scheduler.tick = 1;
+
+        assert_eq!(
+            sorted(scheduler.pending_in_chunk((0, 0, 0))),
+            vec![(1, 2, 3, 4)]
+        );
+        assert_eq!(
+            sorted(scheduler.pending_in_chunk((-1, 1, 0))),
+            vec![(15, 4, 3, 1)]
+        );
+        assert!(scheduler.pending_in_chunk((5, 0, 5)).is_empty());
+    }
+
+    #[test]
+    fn due_ticks_come_out_in_order() {
+        let mut scheduler = TickScheduler::new();
+        scheduler.schedule((0, 0, 0), 3);
+        scheduler.schedule((1, 0, 0), 1);
+        scheduler.schedule((2, 0, 0), 2);
+
+        scheduler.tick = 2;
+        assert_eq!(scheduler.pop_due(), Some((1, 0, 0)));
+        assert_eq!(scheduler.pop_due(), Some((2, 0, 0)));
+        assert_eq!(scheduler.pop_due(), None);
+        scheduler.tick = 3;
+        assert_eq!(scheduler.pop_due(), Some((0, 0, 0)));
+        assert!(scheduler.pending_in_chunk((0, 0, 0)).is_empty());
+    }
+
+    #[test]
+    fn removed_chunks_lose_their_ticks() {
+        let mut scheduler = TickScheduler::new();
+        scheduler.schedule((1, 1, 1), 1);
+        scheduler.schedule((17, 1, 1), 1);
+        scheduler.remove_chunks(&HashSet::from([(0, 0, 0)]));
+        assert!(scheduler.pending_in_chunk((0, 0, 0)).is_empty());
+
+        // 다시 불러온 청크의 업데이트는 새로 예약된 틱에 한 번만 실행된다
+        scheduler.restore(
+            (0, 0, 0),
+            &[PendingTick {
+                x: 1,
+                y: 1,
+                z: 1,
+                delay: 3,
+            }],
+        );
+        scheduler.tick = 1;
+        assert_eq!(scheduler.pop_due(), Some((17, 1, 1)));
+        assert_eq!(scheduler.pop_due(), None);
+        scheduler.tick = 3;
+        assert_eq!(scheduler.pop_due(), Some((1, 1, 1)));
+        assert_eq!(scheduler.pop_due(), None);
+    }
+}
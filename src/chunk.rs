@@ -1,97 +1,65 @@
+use crate::block_registry::{BlockDefinition, FluidState, BLOCKS};
+use crate::block_storage::BlockStorage;
+use crate::block_ticks::PendingTick;
 use crate::chunk_manager::{CHUNK_SIZE, CHUNK_VOLUME};
-use crate::gl_call;
+use crate::lighting::{self, LightStorage};
 use rand::distributions::Standard;
 use rand::prelude::Distribution;
 use rand::random;
 use std::collections::HashSet;
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash)]
-pub enum BlockID {
-    Air,
-    Dirt,
-    GrassBlock,
-    Cobblestone,
-    Obsidian,
-    OakLog,
-    OakLeaves,
-    Debug,
-    Debug2,
-}
+// blocks/blocks.json에 등록된 순서대로 번호가 매겨진다 (0은 항상 공기)
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct BlockID(pub u16);
 
 impl BlockID {
+    pub const AIR: BlockID = BlockID(0);
+
     pub fn is_air(&self) -> bool {
-        self == &BlockID::Air
+        self == &BlockID::AIR
+    }
+
+    pub fn definition(&self) -> &'static BlockDefinition {
+        BLOCKS.get(*self)
     }
 
     pub fn is_transparent(&self) -> bool {
-        match self {
-            BlockID::Air | BlockID::OakLeaves => true,
-            _ => false,
-        }
+        self.definition().transparent
+    }
+
+    pub fn is_solid(&self) -> bool {
+        self.definition().solid
+    }
+
+    pub fn fluid_state(&self) -> Option<FluidState> {
+        self.definition().fluid_state
+    }
+
+    pub fn is_fluid(&self) -> bool {
+        self.fluid_state().is_some()
     }
 }
 
 impl Distribution<BlockID> for Standard {
     fn sample<R: rand::prelude::Rng + ?Sized>(&self, rng: &mut R) -> BlockID {
-        match rng.gen_range(1..4) {
-            //0 => BlockID::AIR,
-            1 => BlockID::Dirt,
-            2 => BlockID::Cobblestone,
-            3 => BlockID::Obsidian,
-            _ => BlockID::Air,
+        // any registered block except air - air itself if nothing else is registered
+        if BLOCKS.len() <= 1 {
+            return BlockID::AIR;
         }
+        BlockID(rng.gen_range(1..BLOCKS.len()) as u16)
     }
 }
 
-fn create_vao_vbo() -> (u32, u32) {
-    let mut vao = 0;
-    gl_call!(gl::CreateVertexArrays(1, &mut vao));
-
-    // pos
-    gl_call!(gl::EnableVertexArrayAttrib(vao, 0));
-    gl_call!(gl::VertexArrayAttribFormat(
-        vao,
-        0,
-        3_i32,
-        gl::FLOAT,
-        gl::FALSE,
-        0
-    ));
-    gl_call!(gl::VertexArrayAttribBinding(vao, 0, 0));
-
-    // texture
-    gl_call!(gl::EnableVertexArrayAttrib(vao, 1));
-    gl_call!(gl::VertexArrayAttribFormat(
-        vao,
-        1,
-        2_i32,
-        gl::FLOAT,
-        gl::FALSE,
-        (3 * std::mem::size_of::<f32>()) as u32
-    ));
-    gl_call!(gl::VertexArrayAttribBinding(vao, 1, 0));
-
-    let mut vbo = 0;
-    gl_call!(gl::CreateBuffers(1, &mut vbo));
-
-    gl_call!(gl::VertexArrayVertexBuffer(
-        vao,
-        0,
-        vbo,
-        0,
-        (5 * std::mem::size_of::<f32>()) as i32
-    ));
-
-    (vao, vbo)
-}
-
+// 청크의 블록 데이터만 가진다 (GPU 메시는 chunk_mesh::ChunkMesh)
+#[derive(Clone)]
 pub struct Chunk {
-    blocks: [BlockID; CHUNK_VOLUME as usize],
-    pub vao: u32,
-    pub vbo: u32,
-    pub vertices_drawn: u32, // 그려진 vertex의 수
-    pub dirty: bool,         // 데이터 변경 여부
+    blocks: BlockStorage,
+    light: LightStorage,
+    pub dirty: bool, // 데이터 변경 여부
     pub dirty_neighbours: HashSet<(i32, i32, i32)>,
+    pub modified: bool, // 디스크에 저장된 내용과 달라졌는지 여부
+    // 디스크와 주고받는 동안만 쓰인다 - 불러온 뒤에는 ChunkManager의 TickScheduler가 가진다
+    pub scheduled_ticks: Vec<PendingTick>,
 }
 
 impl Chunk {
@@ -109,50 +77,61 @@ impl Chunk {
     }
 
     pub fn empty() -> Chunk {
-        let (vao, vbo) = create_vao_vbo();
-
         Chunk {
-            blocks: [BlockID::Air; CHUNK_VOLUME as usize],
-            vao,
-            vbo,
-            vertices_drawn: 0,
+            blocks: BlockStorage::Single(BlockID::AIR),
+            light: LightStorage::Uniform(0),
             dirty: false,
             dirty_neighbours: Chunk::all_neighbours(),
+            modified: false,
+            scheduled_ticks: Vec::new(),
         }
     }
 
     pub fn full_of_block(block: BlockID) -> Chunk {
-        let (vao, vbo) = create_vao_vbo();
-
         Chunk {
-            blocks: [block; CHUNK_VOLUME as usize],
-            vao,
-            vbo,
-            vertices_drawn: 0,
+            blocks: BlockStorage::Single(block),
+            light: LightStorage::Uniform(0),
             dirty: true,
             dirty_neighbours: Chunk::all_neighbours(),
+            modified: false,
+            scheduled_ticks: Vec::new(),
         }
     }
 
     pub fn random() -> Chunk {
-        let (vao, vbo) = create_vao_vbo();
-
         let mut chunk = Chunk {
-            blocks: [BlockID::Air; CHUNK_VOLUME as usize],
-            vao,
-            vbo,
-            vertices_drawn: 0,
+            blocks: BlockStorage::Single(BlockID::AIR),
+            light: LightStorage::Uniform(0),
             dirty: true,
             dirty_neighbours: Chunk::all_neighbours(),
+            modified: false,
+            scheduled_ticks: Vec::new(),
         };
 
-        for i in 0..chunk.blocks.len() {
-            chunk.blocks[i] = random::<BlockID>();
+        for i in 0..CHUNK_VOLUME as usize {
+            chunk.blocks.set(i, random::<BlockID>());
         }
 
         chunk
     }
 
+    pub fn from_storage(blocks: BlockStorage) -> Chunk {
+        let mut chunk = Chunk::empty();
+        chunk.blocks = blocks;
+        chunk.dirty = true;
+
+        chunk
+    }
+
+    pub fn blocks(&self) -> &BlockStorage {
+        &self.blocks
+    }
+
+    // 팔레트에서 안 쓰는 블록을 정리한다 (한 종류만 남으면 Single)
+    pub fn optimize_storage(&mut self) {
+        self.blocks.optimize();
+    }
+
     #[inline]
     fn coords_to_index(x: u32, y: u32, z: u32) -> usize {
         (y * (CHUNK_SIZE * CHUNK_SIZE) + z * (CHUNK_SIZE) + x) as usize
@@ -160,12 +139,18 @@ impl Chunk {
 
     #[inline]
     pub fn get_block(&self, x: u32, y: u32, z: u32) -> BlockID {
-        self.blocks[Chunk::coords_to_index(x, y, z)]
+        self.blocks.get(Chunk::coords_to_index(x, y, z))
     }
 
     #[inline]
     pub fn set_block(&mut self, x: u32, y: u32, z: u32, block: BlockID) {
-        self.blocks[Chunk::coords_to_index(x, y, z)] = block;
+        self.blocks.set(Chunk::coords_to_index(x, y, z), block);
+        self.modified = true;
+        self.mark_dirty(x, y, z);
+    }
+
+    // 블록 하나가 바뀌었을 때 이 청크와 (경계라면) 이웃 청크를 다시 메시로 만들게 한다
+    fn mark_dirty(&mut self, x: u32, y: u32, z: u32) {
         self.dirty = true;
 
         if x == 0 {
@@ -186,4 +171,47 @@ impl Chunk {
             self.dirty_neighbours.insert((0, 0, 1));
         }
     }
+
+    // 하늘빛과 블록 빛을 한 바이트로 묶은 값 (lighting::sky_light, lighting::block_light로 꺼낸다)
+    #[inline]
+    pub fn get_light(&self, x: u32, y: u32, z: u32) -> u8 {
+        self.light.get(Chunk::coords_to_index(x, y, z))
+    }
+
+    #[inline]
+    pub fn sky_light(&self, x: u32, y: u32, z: u32) -> u8 {
+        lighting::sky_light(self.get_light(x, y, z))
+    }
+
+    #[inline]
+    pub fn block_light(&self, x: u32, y: u32, z: u32) -> u8 {
+        lighting::block_light(self.get_light(x, y, z))
+    }
+
+    // 빛은 불러올 때 다시 계산하므로 바꿔도 modified가 되지 않는다
+    pub fn set_sky_light(&mut self, x: u32, y: u32, z: u32, level: u8) {
+        let light = self.get_light(x, y, z);
+        if lighting::sky_light(light) != level {
+            let packed = lighting::pack_light(level, lighting::block_light(light));
+            self.light.set(Chunk::coords_to_index(x, y, z), packed);
+            self.mark_dirty(x, y, z);
+        }
+    }
+
+    pub fn set_block_light(&mut self, x: u32, y: u32, z: u32, level: u8) {
+        let light = self.get_light(x, y, z);
+        if lighting::block_light(light) != level {
+            let packed = lighting::pack_light(lighting::sky_light(light), level);
+            self.light.set(Chunk::coords_to_index(x, y, z), packed);
+            self.mark_dirty(x, y, z);
+        }
+    }
+
+    pub fn fill_light(&mut self, light: u8) {
+        self.light = LightStorage::Uniform(light);
+    }
+
+    pub fn optimize_light(&mut self) {
+        self.light.optimize();
+    }
 }
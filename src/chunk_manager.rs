@@ -1,30 +1,215 @@
-use crate::block_texture_sides::{get_uv_every_side, BlockFaces};
-use crate::shader::ShaderProgram;
-use crate::UVCoords;
-use crate::{
-    chunk::{BlockID, Chunk},
-    shapes::write_unit_cube_to_ptr,
+use crate::aabb::AABB;
+use crate::biome::Biome;
+use crate::block_ticks::{self, TickScheduler};
+use crate::chunk::{BlockID, Chunk};
+use crate::chunk_mesh::{ChunkMesh, LayeredChunkMesh, MeshArena, MAX_QUADS_PER_MESH};
+use crate::constants::{
+    MAX_CHUNKS_REBUILT_PER_FRAME, MAX_COLUMNS_GENERATED_PER_FRAME, MAX_LIGHT_UPDATES_PER_FRAME,
+    MAX_SCHEDULED_TICKS_PER_TICK, PHYSICS_TICKRATE, PHYSICS_TICKS_PER_WORLD_TICK,
+    RANDOM_TICK_SPEED, RENDER_DISTANCE, WORLD_DIRECTORY, WORLD_HEIGHT_CHUNKS,
+};
+use crate::entities::{EntityEvent, EntityInstance, EntityManager};
+use crate::explosion::{self, Explosion};
+use crate::frustum::Frustum;
+use crate::lighting::{self, LightUpdates, MAX_LIGHT};
+use crate::lod::{LodManager, LodSlice, LodTile};
+use crate::mesher::{
+    build_block_mesh, build_mesh, MeshLayers, MeshingStrategy, PaddedChunk, RenderLayer,
 };
+use crate::region::RegionStorage;
+use crate::shader::ShaderProgram;
+use crate::visibility::{self, ChunkVisibility};
+use crate::worker_pool::WorkerPool;
+use crate::world_gen::{ColumnAccess, WorldGenerator};
+use crate::UVMap;
 use nalgebra::Matrix4;
-use nalgebra_glm::vec3;
-use noise::{NoiseFn, SuperSimplex};
-use rand::random;
+use nalgebra_glm::{vec3, Vec3};
+use rand::{random, thread_rng, Rng};
 use std::borrow::Borrow;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
 
 pub const CHUNK_SIZE: u32 = 16;
 pub const CHUNK_VOLUME: u32 = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
 
 pub type Sides = [bool; 6];
 
+// 워커 스레드에서 생성이 끝난 청크 기둥
+struct GeneratedColumn {
+    column: (i32, i32),
+    chunks: Vec<((i32, i32, i32), Chunk)>,
+    persisted: Vec<(i32, i32, i32)>,
+}
+
+// 워커가 디스크에 쓴 청크 기둥
+struct SavedColumn {
+    column: (i32, i32),
+    result: std::io::Result<()>,
+}
+
+// 워커 스레드에서 생성 중인 청크 기둥 - 디스크에서 불러온 청크는 덮어쓰지 않는다
+struct GeneratingColumn {
+    chunks: HashMap<(i32, i32, i32), Chunk>,
+    persisted: Vec<(i32, i32, i32)>,
+}
+
+impl ColumnAccess for GeneratingColumn {
+    fn get_block(&self, x: i32, y: i32, z: i32) -> Option<BlockID> {
+        let (chunk_x, chunk_y, chunk_z, block_x, block_y, block_z) =
+            ChunkManager::get_chunk_and_block_coords(x, y, z);
+
+        self.chunks
+            .get(&(chunk_x, chunk_y, chunk_z))
+            .map(|chunk| chunk.get_block(block_x, block_y, block_z))
+    }
+
+    fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockID) {
+        let (chunk_x, chunk_y, chunk_z, block_x, block_y, block_z) =
+            ChunkManager::get_chunk_and_block_coords(x, y, z);
+
+        if self.persisted.contains(&(chunk_x, chunk_y, chunk_z)) {
+            return;
+        }
+        if let Some(chunk) = self.chunks.get_mut(&(chunk_x, chunk_y, chunk_z)) {
+            chunk.set_block(block_x, block_y, block_z, block);
+        }
+    }
+}
+
+// 워커 스레드에서 만들어진 메시
+struct MeshedChunk {
+    coords: (i32, i32, i32),
+    ticket: u64,
+    layers: MeshLayers,
+    visibility: ChunkVisibility,
+}
+
+// 지난 프레임에 그린 청크와 건너뛴 청크 수 (그릴 메시가 있는 청크만 센다)
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RenderStats {
+    pub drawn: usize,
+    // 시야(frustum) 밖의 청크
+    pub culled: usize,
+    // 시야 안에 있지만 카메라 청크에서 투명한 블록을 따라 닿을 수 없는 청크 (동굴 컬링)
+    pub occluded: usize,
+    // 멀리 있는 지형을 합쳐서 그린 LOD 타일
+    pub lod_tiles: usize,
+    // 메시 arena에서 쓰고 있는 사각형 수, 전체 크기, 빈 공간이 흩어진 정도 (0..1)
+    pub arena_used: usize,
+    pub arena_capacity: usize,
+    pub arena_fragmentation: f32,
+}
+
 pub struct ChunkManager {
     pub loaded_chunks: HashMap<(i32, i32, i32), Chunk>,
+    pub meshes: HashMap<(i32, i32, i32), LayeredChunkMesh>,
+    // 청크와 LOD 타일의 메시가 모두 들어 있는 GPU 버퍼 - 처음 메시를 올릴 때 만든다
+    arena: Option<MeshArena>,
+    // 메시를 만들 때 함께 계산한, 청크의 면끼리 이어졌는지 여부
+    visibility: HashMap<(i32, i32, i32), ChunkVisibility>,
+    pub storage: RegionStorage,
+    pub render_distance: i32,
+    pub meshing_strategy: MeshingStrategy,
+    generator: Arc<WorldGenerator>,
+    loaded_columns: HashSet<(i32, i32)>,
+    pending_columns: HashSet<(i32, i32)>,
+    // 디스크에서 불러온 청크 - 지형 생성이 덮어쓰지 않는다
+    persisted_chunks: HashSet<(i32, i32, i32)>,
+    center: (i32, i32, i32),
+    workers: WorkerPool,
+    generated_sender: Sender<GeneratedColumn>,
+    generated_receiver: Receiver<GeneratedColumn>,
+    // 내린 뒤 워커가 아직 디스크에 쓰고 있는 기둥 - 쓰기가 끝날 때까지 다시 불러오지 않는다
+    saving: HashMap<(i32, i32), Arc<Vec<((i32, i32, i32), Chunk)>>>,
+    saved_sender: Sender<SavedColumn>,
+    saved_receiver: Receiver<SavedColumn>,
+    meshed_sender: Sender<MeshedChunk>,
+    meshed_receiver: Receiver<MeshedChunk>,
+    // 늦게 끝난 오래된 메시가 새 메시를 덮어쓰지 않도록 메시 작업마다 번호를 붙인다
+    next_mesh_ticket: u64,
+    mesh_tickets: HashMap<(i32, i32, i32), u64>,
+    // 블록이 바뀐 뒤 아직 끝나지 않은 빛 계산 - 프레임마다 조금씩 한다
+    light_updates: LightUpdates,
+    // 틱마다 청크 하나에서 무작위 틱을 받는 블록 수
+    pub random_tick_speed: u32,
+    pub scheduler: TickScheduler,
+    pub entities: EntityManager,
+    // 지금까지 진행한 물리 틱 수 - 엔티티는 물리 틱마다, 블록은 월드 틱마다 업데이트된다
+    physics_tick: u64,
+    entity_mesh: Option<ChunkMesh>,
+    // 이번 프레임에 일어난 폭발 - main이 플레이어를 밀어낸 뒤 비운다
+    pub explosions: Vec<Explosion>,
+    // 이번 프레임에 시야 안에 있는 청크 - 불투명한 면을 그릴 때 정하고 반투명한 면도 이것만 그린다
+    visible_chunks: Vec<(i32, i32, i32)>,
+    // 불러온 청크 밖의 지형 - 블록 데이터 없이 높이맵으로 만든 메시
+    lod: LodManager,
+    visible_lod_tiles: Vec<LodTile>,
+    pub render_stats: RenderStats,
 }
 
 impl ChunkManager {
+    // 저장된 월드가 있으면 그 시드를, 없으면 새 시드를 만들어 저장한다
     pub fn new() -> ChunkManager {
+        let storage = RegionStorage::new(WORLD_DIRECTORY);
+        let seed = match storage.load_seed() {
+            Ok(Some(seed)) => seed,
+            Ok(None) => {
+                let seed = random();
+                if let Err(err) = storage.save_seed(seed) {
+                    eprintln!("Failed to save world seed: {err}");
+                }
+                seed
+            }
+            Err(err) => {
+                // 시드 파일은 덮어쓰지 않는다 - 파일을 고치면 원래 월드로 돌아갈 수 있다
+                eprintln!("Failed to load world seed, using a temporary one: {err}");
+                random()
+            }
+        };
+
+        ChunkManager::with_storage(storage, seed)
+    }
+
+    pub fn with_storage(storage: RegionStorage, seed: u64) -> ChunkManager {
+        let (generated_sender, generated_receiver) = channel();
+        let (saved_sender, saved_receiver) = channel();
+        let (meshed_sender, meshed_receiver) = channel();
+
         ChunkManager {
             loaded_chunks: HashMap::new(),
+            meshes: HashMap::new(),
+            arena: None,
+            visibility: HashMap::new(),
+            storage,
+            render_distance: RENDER_DISTANCE,
+            meshing_strategy: MeshingStrategy::Greedy,
+            generator: Arc::new(WorldGenerator::new(seed)),
+            loaded_columns: HashSet::new(),
+            pending_columns: HashSet::new(),
+            persisted_chunks: HashSet::new(),
+            center: (0, 0, 0),
+            workers: WorkerPool::with_available_parallelism(),
+            generated_sender,
+            generated_receiver,
+            saving: HashMap::new(),
+            saved_sender,
+            saved_receiver,
+            meshed_sender,
+            meshed_receiver,
+            next_mesh_ticket: 1,
+            mesh_tickets: HashMap::new(),
+            light_updates: LightUpdates::default(),
+            random_tick_speed: RANDOM_TICK_SPEED,
+            scheduler: TickScheduler::new(),
+            entities: EntityManager::new(),
+            physics_tick: 0,
+            entity_mesh: None,
+            explosions: Vec::new(),
+            visible_chunks: Vec::new(),
+            lod: LodManager::new(),
+            visible_lod_tiles: Vec::new(),
+            render_stats: RenderStats::default(),
         }
     }
 
@@ -38,64 +223,270 @@ impl ChunkManager {
         }
     }
 
-    pub fn simplex(&mut self) {
-        let ss = SuperSimplex::new(1296);
-        let n = 10;
+    pub fn get_chunk_coords(x: f32, y: f32, z: f32) -> (i32, i32, i32) {
+        let (chunk_x, chunk_y, chunk_z, ..) = ChunkManager::get_chunk_and_block_coords(
+            x.floor() as i32,
+            y.floor() as i32,
+            z.floor() as i32,
+        );
+
+        (chunk_x, chunk_y, chunk_z)
+    }
+
+    pub fn is_column_loaded(&self, chunk_x: i32, chunk_z: i32) -> bool {
+        self.loaded_columns.contains(&(chunk_x, chunk_z))
+    }
+
+    fn is_column_in_range(&self, (x, z): (i32, i32), distance: i32) -> bool {
+        let (dx, dz) = (x - self.center.0, z - self.center.2);
+        dx * dx + dz * dz <= distance * distance
+    }
+
+    // 플레이어 주변의 청크를 가까운 기둥부터 불러오고, 멀어진 청크는 저장한 뒤 내린다
+    pub fn update_loaded_chunks(&mut self, center: (i32, i32, i32)) {
+        self.center = center;
+        self.receive_generated_columns();
+        self.receive_saved_columns();
+
+        let (center_x, _, center_z) = center;
+        let r = self.render_distance;
+
+        // 경계에서 청크가 계속 불러와지고 내려지지 않도록 한 칸 여유를 둔다
+        let out_of_range: Vec<(i32, i32)> = self
+            .loaded_columns
+            .iter()
+            .filter(|&&column| !self.is_column_in_range(column, r + 1))
+            .copied()
+            .collect();
 
-        for y in 0..16 {
-            for z in -n..=n {
-                for x in -n..=n {
-                    self.loaded_chunks.insert((x, y, z), Chunk::empty());
+        for (x, z) in out_of_range {
+            self.unload_column(x, z);
+        }
+
+        let mut missing = Vec::new();
+        for z in center_z - r..=center_z + r {
+            for x in center_x - r..=center_x + r {
+                let (dx, dz) = (x - center_x, z - center_z);
+                if self.is_column_in_range((x, z), r)
+                    && !self.loaded_columns.contains(&(x, z))
+                    && !self.pending_columns.contains(&(x, z))
+                    && !self.saving.contains_key(&(x, z))
+                {
+                    missing.push((dx * dx + dz * dz, (x, z)));
                 }
             }
         }
+        missing.sort();
 
-        for x in -16 * n..16 * n {
-            for z in -16 * n..16 * n {
-                let (xf, zf) = (x as f64 / 64.0, z as f64 / 64.0);
-                let y = ss.get([xf, zf]);
-                let y = (16.0 * (y + 1.0)) as i32;
+        // 워커가 밀린 작업을 처리하는 동안에는 새 작업을 너무 많이 쌓지 않는다
+        let capacity = (2 * self.workers.size()).saturating_sub(self.pending_columns.len());
 
-                self.set_block(x, y, z, BlockID::GrassBlock);
-                self.set_block(x, y - 1, z, BlockID::Dirt);
-                self.set_block(x, y - 2, z, BlockID::Dirt);
-                self.set_block(x, y - 3, z, BlockID::Cobblestone);
+        for &(_, (x, z)) in missing
+            .iter()
+            .take(MAX_COLUMNS_GENERATED_PER_FRAME.min(capacity))
+        {
+            self.queue_column(x, z);
+        }
+    }
 
-                if random::<u32>() % 100 == 0 {
-                    let h = 5;
-                    for i in y + 1..y + 1 + h {
-                        self.set_block(x, i, z, BlockID::OakLog)
-                    }
+    fn queue_column(&mut self, chunk_x: i32, chunk_z: i32) {
+        self.pending_columns.insert((chunk_x, chunk_z));
 
-                    for yy in y + h - 2..=y + h - 1 {
-                        for xx in x - 2..=x + 2 {
-                            for zz in z - 2..=z + 2 {
-                                if xx != x || zz != z {
-                                    self.set_block(xx, yy, zz, BlockID::OakLeaves);
-                                }
-                            }
-                        }
-                    }
+        let generator = Arc::clone(&self.generator);
+        let storage = self.storage.clone();
+        let sender = self.generated_sender.clone();
 
-                    for xx in x - 1..=x + 1 {
-                        for zz in z - 1..=z + 1 {
-                            if xx != x || zz != z {
-                                self.set_block(xx, y + h, zz, BlockID::OakLeaves);
-                            }
-                        }
-                    }
+        self.workers.execute(move || {
+            let generated = ChunkManager::generate_column(&generator, &storage, chunk_x, chunk_z);
+            // 받는 쪽이 없으면 게임이 끝난 것이므로 무시한다
+            let _ = sender.send(generated);
+        });
+    }
+
+    // 워커 스레드에서 실행된다
+    fn generate_column(
+        generator: &WorldGenerator,
+        storage: &RegionStorage,
+        chunk_x: i32,
+        chunk_z: i32,
+    ) -> GeneratedColumn {
+        let column: Vec<(i32, i32, i32)> = (0..WORLD_HEIGHT_CHUNKS)
+            .map(|y| (chunk_x, y, chunk_z))
+            .collect();
+
+        // 저장된 청크는 디스크에서 불러오고, 나머지만 새로 생성한다
+        let mut saved_chunks = storage
+            .load_chunks(column.iter().copied())
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to load saved chunks: {err}");
+                HashMap::new()
+            });
 
-                    self.set_block(x, y + h + 1, z, BlockID::OakLeaves);
-                    self.set_block(x + 1, y + h + 1, z, BlockID::OakLeaves);
-                    self.set_block(x - 1, y + h + 1, z, BlockID::OakLeaves);
-                    self.set_block(x, y + h + 1, z + 1, BlockID::OakLeaves);
-                    self.set_block(x, y + h + 1, z - 1, BlockID::OakLeaves);
+        let mut chunks = HashMap::new();
+        let mut persisted = Vec::new();
+        for coords in column {
+            let chunk = match saved_chunks.remove(&coords) {
+                Some(saved) => {
+                    persisted.push(coords);
+                    let mut chunk = Chunk::from_storage(saved.blocks);
+                    chunk.scheduled_ticks = saved.scheduled_ticks;
+                    chunk
                 }
+                None => Chunk::empty(),
+            };
+            chunks.insert(coords, chunk);
+        }
+
+        let mut column = GeneratingColumn { chunks, persisted };
+        generator.generate_column(chunk_x, chunk_z, &mut column);
+        let GeneratingColumn {
+            mut chunks,
+            persisted,
+        } = column;
+
+        lighting::light_column(&mut chunks, chunk_x, chunk_z);
+
+        // 새로 만든 지형은 언제든 다시 만들 수 있으므로 그 뒤의 변경만 저장한다
+        for chunk in chunks.values_mut() {
+            chunk.modified = false;
+            chunk.optimize_storage();
+            // 공기만 있는 청크는 그릴 것이 없다
+            chunk.dirty = chunk.blocks().is_uniform() != Some(BlockID::AIR);
+        }
+
+        GeneratedColumn {
+            column: (chunk_x, chunk_z),
+            chunks: chunks.into_iter().collect(),
+            persisted,
+        }
+    }
+
+    fn receive_generated_columns(&mut self) {
+        while let Ok(generated) = self.generated_receiver.try_recv() {
+            self.pending_columns.remove(&generated.column);
+
+            // 생성되는 동안 플레이어가 멀어졌다면 버린다
+            if !self.is_column_in_range(generated.column, self.render_distance + 1) {
+                continue;
+            }
+
+            for (coords, mut chunk) in generated.chunks {
+                let pending = std::mem::take(&mut chunk.scheduled_ticks);
+                self.scheduler.restore(coords, &pending);
+                self.loaded_chunks.insert(coords, chunk);
             }
+            self.persisted_chunks.extend(generated.persisted);
+            self.loaded_columns.insert(generated.column);
+
+            let (chunk_x, chunk_z) = generated.column;
+            lighting::propagate_across_column_borders(&mut self.loaded_chunks, chunk_x, chunk_z);
         }
     }
 
-    fn get_chunk_and_block_coords(x: i32, y: i32, z: i32) -> (i32, i32, i32, u32, u32, u32) {
+    // 저장할 청크에 아직 처리되지 않은 예약 업데이트를 담는다
+    fn attach_scheduled_ticks<'a>(&mut self, chunks: impl Iterator<Item = &'a (i32, i32, i32)>) {
+        for coords in chunks {
+            if let Some(chunk) = self.loaded_chunks.get_mut(coords) {
+                if chunk.modified {
+                    chunk.scheduled_ticks = self.scheduler.pending_in_chunk(*coords);
+                }
+            }
+        }
+    }
+
+    fn unload_column(&mut self, chunk_x: i32, chunk_z: i32) {
+        let column: Vec<(i32, i32, i32)> = (0..WORLD_HEIGHT_CHUNKS)
+            .map(|y| (chunk_x, y, chunk_z))
+            .collect();
+
+        self.attach_scheduled_ticks(column.iter());
+        self.scheduler
+            .remove_chunks(&column.iter().copied().collect());
+
+        let mut modified = Vec::new();
+        for coords in column {
+            if let Some(chunk) = self.loaded_chunks.remove(&coords) {
+                if chunk.modified {
+                    modified.push((coords, chunk));
+                }
+            }
+            if let (Some(mut mesh), Some(arena)) = (self.meshes.remove(&coords), &mut self.arena) {
+                mesh.release(arena);
+            }
+            self.visibility.remove(&coords);
+            self.mesh_tickets.remove(&coords);
+            self.persisted_chunks.remove(&coords);
+        }
+        self.loaded_columns.remove(&(chunk_x, chunk_z));
+
+        if !modified.is_empty() {
+            let chunks = Arc::new(modified);
+            self.saving.insert((chunk_x, chunk_z), Arc::clone(&chunks));
+            self.queue_save((chunk_x, chunk_z), chunks);
+        }
+    }
+
+    fn queue_save(&mut self, column: (i32, i32), chunks: Arc<Vec<((i32, i32, i32), Chunk)>>) {
+        let storage = self.storage.clone();
+        let sender = self.saved_sender.clone();
+
+        self.workers.execute(move || {
+            let result = storage.save_chunks(chunks.iter().map(|(coords, chunk)| (*coords, chunk)));
+            let _ = sender.send(SavedColumn { column, result });
+        });
+    }
+
+    fn receive_saved_columns(&mut self) {
+        while let Ok(saved) = self.saved_receiver.try_recv() {
+            let (chunk_x, chunk_z) = saved.column;
+            match saved.result {
+                Ok(()) => {
+                    self.saving.remove(&saved.column);
+                }
+                // 저장에 실패한 기둥은 갖고 있다가 다시 시도한다
+                Err(err) => {
+                    eprintln!("Failed to save chunk column ({chunk_x}, {chunk_z}): {err}");
+                    if let Some(chunks) = self.saving.get(&saved.column) {
+                        self.queue_save(saved.column, Arc::clone(chunks));
+                    }
+                }
+            }
+        }
+    }
+
+    // 게임을 끝낼 때 - 워커가 쓰고 있는 기둥을 기다린 다음, 불러온 청크의 변경을 모두 쓴다
+    pub fn save(&mut self) -> std::io::Result<()> {
+        while !self.saving.is_empty() {
+            let saved = match self.saved_receiver.recv() {
+                Ok(saved) => saved,
+                Err(_) => break,
+            };
+            self.saving.remove(&saved.column);
+            saved.result?;
+        }
+
+        let modified: Vec<(i32, i32, i32)> = self
+            .loaded_chunks
+            .iter()
+            .filter(|(_, chunk)| chunk.modified)
+            .map(|(&coords, _)| coords)
+            .collect();
+        self.attach_scheduled_ticks(modified.iter());
+        self.storage.save_chunks(
+            modified
+                .iter()
+                .map(|coords| (*coords, &self.loaded_chunks[coords])),
+        )?;
+
+        for chunk in self.loaded_chunks.values_mut() {
+            chunk.modified = false;
+            chunk.scheduled_ticks.clear();
+        }
+
+        Ok(())
+    }
+
+    pub fn get_chunk_and_block_coords(x: i32, y: i32, z: i32) -> (i32, i32, i32, u32, u32, u32) {
         let chunk_x = if x < 0 { (x + 1) / 16 - 1 } else { x / 16 };
         let chunk_y = if y < 0 { (y + 1) / 16 - 1 } else { y / 16 };
         let chunk_z = if z < 0 { (z + 1) / 16 - 1 } else { z / 16 };
@@ -107,14 +498,8 @@ impl ChunkManager {
         (chunk_x, chunk_y, chunk_z, block_x, block_y, block_z)
     }
 
-    fn get_global_coords(
-        (chunk_x, chunk_y, chunk_z, block_x, block_y, block_z): (i32, i32, i32, u32, u32, u32),
-    ) -> (i32, i32, i32) {
-        let x = 16 * chunk_x + block_x as i32;
-        let y = 16 * chunk_y + block_y as i32;
-        let z = 16 * chunk_z + block_z as i32;
-
-        (x, y, z)
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome {
+        self.generator.biome_at(x, z)
     }
 
     pub fn get_block(&self, x: i32, y: i32, z: i32) -> Option<BlockID> {
@@ -127,44 +512,177 @@ impl ChunkManager {
     }
 
     pub fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockID) {
+        self.set_blocks(&[((x, y, z), block)]);
+    }
+
+    // 여러 블록을 한꺼번에 바꾼다 - 빛 계산은 큐에 넣어 다음 rebuild_dirty_chunks부터 나눠 하고,
+    // 청크는 한 번씩만 다시 메시를 만든다
+    pub fn set_blocks(&mut self, edits: &[((i32, i32, i32), BlockID)]) {
+        for &((x, y, z), block) in edits {
+            let (chunk_x, chunk_y, chunk_z, block_x, block_y, block_z) =
+                ChunkManager::get_chunk_and_block_coords(x, y, z);
+
+            self.loaded_chunks
+                .get_mut((chunk_x, chunk_y, chunk_z).borrow())
+                .map(|chunk| chunk.set_block(block_x, block_y, block_z, block));
+        }
+
+        let positions: Vec<(i32, i32, i32)> = edits.iter().map(|&(position, _)| position).collect();
+        self.light_updates
+            .queue(&mut self.loaded_chunks, &positions);
+
+        // 바뀐 블록과 이웃 중 다시 계산해야 하는 블록(유체 등)을 예약한다
+        let mut updates = HashSet::new();
+        for &(x, y, z) in &positions {
+            updates.insert((x, y, z));
+            for (dx, dy, dz) in lighting::NEIGHBOURS {
+                updates.insert((x + dx, y + dy, z + dz));
+            }
+        }
+        for (x, y, z) in updates {
+            self.schedule_update(x, y, z);
+        }
+    }
+
+    pub fn get_light(&self, x: i32, y: i32, z: i32) -> Option<u8> {
         let (chunk_x, chunk_y, chunk_z, block_x, block_y, block_z) =
             ChunkManager::get_chunk_and_block_coords(x, y, z);
 
         self.loaded_chunks
-            .get_mut((chunk_x, chunk_y, chunk_z).borrow())
-            .map(|chunk| chunk.set_block(block_x, block_y, block_z, block));
+            .get(&(chunk_x, chunk_y, chunk_z))
+            .map(|chunk| chunk.get_light(block_x, block_y, block_z))
+    }
+
+    fn schedule_update(&mut self, x: i32, y: i32, z: i32) {
+        if let Some(delay) = self.get_block(x, y, z).and_then(block_ticks::update_delay) {
+            self.schedule_tick(x, y, z, delay);
+        }
     }
 
-    pub fn rebuild_dirty_chunks(&mut self, uv_map: &HashMap<BlockID, BlockFaces<UVCoords>>) {
-        let mut dirty_chunks = HashSet::new();
+    // delay 틱 뒤에 블록 업데이트를 예약한다 - 예약은 청크와 함께 저장된다
+    pub fn schedule_tick(&mut self, x: i32, y: i32, z: i32, delay: u32) {
+        let (chunk_x, chunk_y, chunk_z, ..) = ChunkManager::get_chunk_and_block_coords(x, y, z);
 
-        // Nearby chunks can be also dirty if the change happens at the edge
-        for (&(x, y, z), chunk) in self.loaded_chunks.iter() {
-            if chunk.dirty {
-                dirty_chunks.insert((x, y, z));
+        if let Some(chunk) = self.loaded_chunks.get_mut(&(chunk_x, chunk_y, chunk_z)) {
+            if self.scheduler.schedule((x, y, z), delay) {
+                chunk.modified = true;
             }
+        }
+    }
 
-            for &(dx, dy, dz) in chunk.dirty_neighbours.iter() {
-                dirty_chunks.insert((x + dx, y + dy, z + dz));
+    // 물리 틱이 진행된 만큼 엔티티와 월드 틱을 진행한다
+    pub fn update_ticks(&mut self, physics_ticks: u64) {
+        while self.physics_tick < physics_ticks {
+            self.physics_tick += 1;
+            self.step_entities(1.0 / PHYSICS_TICKRATE);
+
+            if self.physics_tick % PHYSICS_TICKS_PER_WORLD_TICK == 0 {
+                self.run_tick();
+            }
+        }
+    }
+
+    fn step_entities(&mut self, dt: f32) {
+        let chunks = &self.loaded_chunks;
+        let columns = &self.loaded_columns;
+        let get_block = |x: i32, y: i32, z: i32| {
+            let (chunk_x, chunk_y, chunk_z, block_x, block_y, block_z) =
+                ChunkManager::get_chunk_and_block_coords(x, y, z);
+            chunks
+                .get(&(chunk_x, chunk_y, chunk_z))
+                .map(|chunk| chunk.get_block(block_x, block_y, block_z))
+        };
+        let events = self
+            .entities
+            .step(dt, &get_block, &|x, z| columns.contains(&(x, z)));
+
+        for event in events {
+            match event {
+                EntityEvent::BlockLanded { x, y, z, block } => match self.get_block(x, y, z) {
+                    Some(target) if block_ticks::can_land_in(target) => {
+                        self.set_block(x, y, z, block)
+                    }
+                    Some(_) => self.entities.spawn_item(x, y, z, block),
+                    None => {}
+                },
+                EntityEvent::Exploded(explosion) => {
+                    explosion::explode(self, explosion, &mut thread_rng())
+                }
             }
         }
+    }
 
-        let mut active_sides: HashMap<(i32, i32, i32), Vec<Sides>> = HashMap::new();
+    fn run_tick(&mut self) {
+        self.scheduler.tick += 1;
 
-        for &coords in dirty_chunks.iter() {
-            let (cx, cy, cz) = coords;
-            let chunk = self.loaded_chunks.get(&coords);
+        // 처리하지 못한 업데이트는 다음 틱으로 미뤄진다
+        for _ in 0..MAX_SCHEDULED_TICKS_PER_TICK {
+            match self.scheduler.pop_due() {
+                Some((x, y, z)) => {
+                    // 저장된 청크에 남아 있는 업데이트가 다시 불러올 때 또 실행되지 않게 한다
+                    let (chunk_x, chunk_y, chunk_z, ..) =
+                        ChunkManager::get_chunk_and_block_coords(x, y, z);
+                    if let Some(chunk) = self.loaded_chunks.get_mut(&(chunk_x, chunk_y, chunk_z)) {
+                        chunk.modified = true;
+                    }
+                    block_ticks::scheduled_tick(self, x, y, z);
+                }
+                None => break,
+            }
+        }
 
-            if let Some(chunk) = chunk {
-                let sides_vec = active_sides.entry(coords).or_default();
+        // 무작위 틱을 받을 블록이 없는 청크(공기나 돌로만 된 청크 등)는 건너뛴다
+        let chunks: Vec<(i32, i32, i32)> = self
+            .loaded_chunks
+            .iter()
+            .filter(|(_, chunk)| {
+                chunk
+                    .blocks()
+                    .is_uniform()
+                    .map_or(true, |block| block.definition().random_tick.is_some())
+            })
+            .map(|(&coords, _)| coords)
+            .collect();
 
-                for by in 0..CHUNK_SIZE {
-                    for bz in 0..CHUNK_SIZE {
-                        for bx in 0..CHUNK_SIZE {
-                            if !chunk.get_block(bx, by, bz).is_air() {
-                                let (gx, gy, gz) =
-                                    ChunkManager::get_global_coords((cx, cy, cz, bx, by, bz));
-                                sides_vec.push(self.get_active_sides_of_block(gx, gy, gz));
+        let size = CHUNK_SIZE as i32;
+        let mut rng = thread_rng();
+        for (cx, cy, cz) in chunks {
+            for _ in 0..self.random_tick_speed {
+                let x = cx * size + rng.gen_range(0..size);
+                let y = cy * size + rng.gen_range(0..size);
+                let z = cz * size + rng.gen_range(0..size);
+                block_ticks::random_tick(self, x, y, z, &mut rng);
+            }
+        }
+    }
+
+    // 청크와 주변 26개 청크의 맞닿은 블록을 복사한다
+    fn padded_chunk(&self, (cx, cy, cz): (i32, i32, i32)) -> PaddedChunk {
+        let size = CHUNK_SIZE as i32;
+        let mut padded = PaddedChunk::new();
+
+        // 이웃 방향(-1, 0, 1)마다 복사해야 하는 그 청크 안의 좌표 범위
+        let range = |d: i32| match d {
+            -1 => size - 1..size,
+            0 => 0..size,
+            _ => 0..1,
+        };
+
+        for dy in -1..=1 {
+            for dz in -1..=1 {
+                for dx in -1..=1 {
+                    let chunk = match self.loaded_chunks.get(&(cx + dx, cy + dy, cz + dz)) {
+                        Some(chunk) => chunk,
+                        None => continue,
+                    };
+
+                    for y in range(dy) {
+                        for z in range(dz) {
+                            for x in range(dx) {
+                                let (px, py, pz) = (x + dx * size, y + dy * size, z + dz * size);
+                                let (x, y, z) = (x as u32, y as u32, z as u32);
+                                padded.set(px, py, pz, chunk.get_block(x, y, z));
+                                padded.set_light(px, py, pz, chunk.get_light(x, y, z));
                             }
                         }
                     }
@@ -172,120 +690,353 @@ impl ChunkManager {
             }
         }
 
-        for coords in dirty_chunks.iter() {
-            let chunk = self.loaded_chunks.get_mut(coords);
+        padded
+    }
 
-            if let Some(chunk) = chunk {
-                chunk.dirty = false;
-                chunk.dirty_neighbours.clear();
-                chunk.vertices_drawn = 0;
+    pub fn rebuild_dirty_chunks(&mut self, uv_map: &Arc<UVMap>) {
+        self.light_updates
+            .run(&mut self.loaded_chunks, MAX_LIGHT_UPDATES_PER_FRAME);
 
-                let sides = active_sides.get(coords).unwrap();
-                let n_visible_faces = sides
-                    .iter()
-                    .map(|faces| faces.iter().fold(0, |acc, &x| acc + x as u32))
-                    .fold(0, |acc, n| acc + n);
+        // 워커가 끝낸 메시를 업로드한다
+        while let Ok(meshed) = self.meshed_receiver.try_recv() {
+            if !self.loaded_chunks.contains_key(&meshed.coords) {
+                continue;
+            }
 
-                if n_visible_faces == 0 {
-                    continue;
-                }
+            let latest = self.mesh_tickets.entry(meshed.coords).or_insert(0);
+            if meshed.ticket < *latest {
+                continue;
+            }
+            *latest = meshed.ticket;
+            self.visibility.insert(meshed.coords, meshed.visibility);
 
-                gl_call!(gl::NamedBufferData(
-                    chunk.vbo,
-                    (180 * std::mem::size_of::<f32>() * n_visible_faces as usize) as isize,
-                    std::ptr::null(),
-                    gl::DYNAMIC_DRAW
-                ));
+            let empty = meshed.layers.iter().all(|vertices| vertices.is_empty());
+            if empty && !self.meshes.contains_key(&meshed.coords) {
+                continue;
+            }
 
-                let vbo_ptr = gl_call!(gl::MapNamedBuffer(chunk.vbo, gl::WRITE_ONLY)) as *mut f32;
-                let mut idx = 0;
+            self.meshes
+                .entry(meshed.coords)
+                .or_insert_with(LayeredChunkMesh::new)
+                .upload(self.arena.get_or_insert_with(MeshArena::new), meshed.layers);
+        }
 
-                let sides_vec = active_sides.get(coords).unwrap();
-                let mut cnt = 0;
+        // Nearby chunks can be also dirty if the change happens at the edge
+        let mut dirty_neighbours = Vec::new();
+        for (&(x, y, z), chunk) in self.loaded_chunks.iter_mut() {
+            for &(dx, dy, dz) in chunk.dirty_neighbours.iter() {
+                dirty_neighbours.push((x + dx, y + dy, z + dz));
+            }
+            chunk.dirty_neighbours.clear();
+        }
 
-                for y in 0..CHUNK_SIZE {
-                    for z in 0..CHUNK_SIZE {
-                        for x in 0..CHUNK_SIZE {
-                            let block = chunk.get_block(x, y, z);
+        for coords in dirty_neighbours {
+            if let Some(chunk) = self.loaded_chunks.get_mut(&coords) {
+                chunk.dirty = true;
+            }
+        }
 
-                            if block != BlockID::Air {
-                                let active_sides = sides_vec[cnt];
+        // 프레임이 끊기지 않도록 플레이어와 가까운 청크부터 정해진 수만큼만 워커에 넘긴다
+        let (center_x, center_y, center_z) = self.center;
+        let mut dirty_chunks: Vec<(i32, i32, i32)> = self
+            .loaded_chunks
+            .iter()
+            .filter(|(_, chunk)| chunk.dirty)
+            .map(|(&coords, _)| coords)
+            .collect();
+        dirty_chunks.sort_by_key(|&(x, y, z)| {
+            let (dx, dy, dz) = (x - center_x, y - center_y, z - center_z);
+            dx * dx + dy * dy + dz * dz
+        });
+        dirty_chunks.truncate(MAX_CHUNKS_REBUILT_PER_FRAME);
 
-                                let uvs = uv_map.get(&block).unwrap().clone();
-                                let uvs = get_uv_every_side(uvs);
+        for coords in dirty_chunks {
+            self.loaded_chunks.get_mut(&coords).unwrap().dirty = false;
 
-                                let copied_vertices = unsafe {
-                                    write_unit_cube_to_ptr(
-                                        vbo_ptr.offset(idx),
-                                        (x as f32, y as f32, z as f32),
-                                        uvs,
-                                        active_sides,
-                                    )
-                                };
+            let padded = self.padded_chunk(coords);
+            let ticket = self.next_mesh_ticket;
+            self.next_mesh_ticket += 1;
 
-                                chunk.vertices_drawn += copied_vertices;
-                                idx += copied_vertices as isize * 5;
-                                cnt += 1;
-                            }
-                        }
+            let uv_map = Arc::clone(uv_map);
+            let sender = self.meshed_sender.clone();
+            let strategy = self.meshing_strategy;
+
+            self.workers.execute(move || {
+                let layers = build_mesh(&padded, &uv_map, strategy);
+                let visibility = ChunkVisibility::compute(&padded);
+                let _ = sender.send(MeshedChunk {
+                    coords,
+                    ticket,
+                    layers,
+                    visibility,
+                });
+            });
+        }
+
+        let (center_x, _, center_z) = self.center;
+        self.lod.update(
+            (center_x, center_z),
+            &self.loaded_columns,
+            &self.generator,
+            &self.workers,
+            uv_map,
+            self.arena.get_or_insert_with(MeshArena::new),
+        );
+    }
+
+    // 메시 안 좌표를 월드 좌표로 - 엔티티마다 위치와 크기를 바꾼다
+    fn model_matrix(origin: Vec3, scale: f32) -> Matrix4<f32> {
+        let translate_matrix = Matrix4::new_translation(&origin);
+        let rotate_matrix = Matrix4::from_euler_angles(0.0f32, 0.0, 0.0);
+        let scale_matrix = Matrix4::new_nonuniform_scaling(&vec3(scale, scale, scale));
+
+        translate_matrix * rotate_matrix * scale_matrix
+    }
+
+    // 메시의 한 단계를 arena의 다음 그리기에 넣는다 - 그릴 것이 없는 단계는 건너뛴다
+    fn queue_layer(
+        arena: &mut MeshArena,
+        origin: Vec3,
+        scale: f32,
+        mesh: &LayeredChunkMesh,
+        layer: RenderLayer,
+    ) {
+        if let Some(id) = mesh.layer(layer) {
+            arena.queue(id, [origin.x, origin.y, origin.z], scale);
+        }
+    }
+
+    fn chunk_origin((x, y, z): (i32, i32, i32)) -> Vec3 {
+        vec3(x as f32, y as f32, z as f32) * CHUNK_SIZE as f32
+    }
+
+    fn lod_origin(tile: &LodTile, base_y: i32) -> Vec3 {
+        let (x, z) = tile.origin();
+        vec3(x as f32, base_y as f32, z as f32)
+    }
+
+    fn lod_aabb(tile: &LodTile) -> AABB {
+        let mins = Self::lod_origin(tile, 0);
+        let height = (WORLD_HEIGHT_CHUNKS * CHUNK_SIZE as i32) as f32;
+        AABB::new(
+            mins,
+            mins + vec3(tile.size() as f32, height, tile.size() as f32),
+        )
+    }
+
+    fn chunk_aabb(coords: (i32, i32, i32)) -> AABB {
+        let mins = Self::chunk_origin(coords);
+        AABB::new(mins, mins.add_scalar(CHUNK_SIZE as f32))
+    }
+
+    // 카메라 청크에서 시작해 청크의 이어진 면을 따라 너비 우선 탐색으로 닿는 청크 (동굴 컬링)
+    fn reachable_chunks(&self, frustum: &Frustum) -> Option<HashSet<(i32, i32, i32)>> {
+        // 월드 위나 아래에서는 가장 가까운 층에서 시작한다
+        let (x, y, z) = self.center;
+        let start = (x, y.clamp(0, WORLD_HEIGHT_CHUNKS - 1), z);
+        if !self.loaded_chunks.contains_key(&start) {
+            return None;
+        }
+
+        let mut reachable = HashSet::from([start]);
+        // (청크, 들어온 면, 지금까지 나아간 방향들)
+        let mut queue = VecDeque::from([(start, None, 0u8)]);
+
+        while let Some((coords, entered, directions)) = queue.pop_front() {
+            let chunk_visibility = self
+                .visibility
+                .get(&coords)
+                .copied()
+                .unwrap_or(ChunkVisibility::ALL);
+
+            for (face, &(dx, dy, dz)) in visibility::FACE_DIRECTIONS.iter().enumerate() {
+                if directions & (1 << visibility::opposite_face(face)) != 0 {
+                    continue;
+                }
+                if let Some(entered) = entered {
+                    if !chunk_visibility.connects(entered, face) {
+                        continue;
                     }
                 }
-                gl_call!(gl::UnmapNamedBuffer(chunk.vbo));
+
+                let next = (coords.0 + dx, coords.1 + dy, coords.2 + dz);
+                if reachable.contains(&next)
+                    || !self.loaded_chunks.contains_key(&next)
+                    || !frustum.intersects_aabb(&Self::chunk_aabb(next))
+                {
+                    continue;
+                }
+
+                reachable.insert(next);
+                queue.push_back((
+                    next,
+                    Some(visibility::opposite_face(face)),
+                    directions | (1 << face),
+                ));
             }
         }
+
+        Some(reachable)
     }
 
-    pub fn get_active_sides_of_block(&self, x: i32, y: i32, z: i32) -> Sides {
-        let right = self
-            .get_block(x + 1, y, z)
-            .filter(|&b| !b.is_transparent())
-            .is_none();
-        let left = self
-            .get_block(x - 1, y, z)
-            .filter(|&b| !b.is_transparent())
-            .is_none();
-        let top = self
-            .get_block(x, y + 1, z)
-            .filter(|&b| !b.is_transparent())
-            .is_none();
-        let bottom = self
-            .get_block(x, y - 1, z)
-            .filter(|&b| !b.is_transparent())
-            .is_none();
-        let front = self
-            .get_block(x, y, z + 1)
-            .filter(|&b| !b.is_transparent())
-            .is_none();
-        let back = self
-            .get_block(x, y, z - 1)
-            .filter(|&b| !b.is_transparent())
-            .is_none();
-
-        [right, left, top, bottom, front, back]
-    }
-
-    pub fn render_loaded_chunks(&mut self, program: &mut ShaderProgram) {
-        for ((x, y, z), chunk) in &self.loaded_chunks {
-            // skip rendering the chunk if there is nothing to draw
-            if chunk.vertices_drawn == 0 {
+    // 불투명한 면과 cutout 면 - 깊이 버퍼가 순서를 정해 주므로 아무 순서로 그린다
+    pub fn render_loaded_chunks(&mut self, program: &mut ShaderProgram, frustum: &Frustum) {
+        let reachable = self.reachable_chunks(frustum);
+        let mut stats = RenderStats::default();
+        self.visible_chunks.clear();
+        for (&coords, mesh) in &self.meshes {
+            if mesh.is_empty() || self.lod.covers((coords.0, coords.2)) {
                 continue;
             }
-            let model_matrix = {
-                let translate_matrix =
-                    Matrix4::new_translation(&vec3(*x as f32, *y as f32, *z as f32).scale(16.0));
-                let rotate_matrix = Matrix4::from_euler_angles(0.0f32, 0.0, 0.0);
-                let scale_matrix = Matrix4::new_nonuniform_scaling(&vec3(1.0f32, 1.0f32, 1.0f32));
+            if !frustum.intersects_aabb(&Self::chunk_aabb(coords)) {
+                stats.culled += 1;
+            } else if reachable.as_ref().map_or(true, |r| r.contains(&coords)) {
+                self.visible_chunks.push(coords);
+            } else {
+                stats.occluded += 1;
+            }
+        }
+        stats.drawn = self.visible_chunks.len();
 
-                translate_matrix * rotate_matrix * scale_matrix
-            };
+        self.visible_lod_tiles = self
+            .lod
+            .meshed_tiles()
+            .filter(|tile| frustum.intersects_aabb(&Self::lod_aabb(tile)))
+            .collect();
+        stats.lod_tiles = self.visible_lod_tiles.len();
+        let arena = self.arena.get_or_insert_with(MeshArena::new);
+        let allocator = arena.allocator();
+        stats.arena_used = allocator.used();
+        stats.arena_capacity = allocator.capacity();
+        stats.arena_fragmentation = allocator.fragmentation();
+        self.render_stats = stats;
+
+        // arena의 메시는 그리기마다 위치와 크기를 따로 받는다
+        program.set_uniform_matrix4fv("model", Matrix4::<f32>::identity().as_ptr());
+        for layer in [RenderLayer::Opaque, RenderLayer::Cutout] {
+            for coords in &self.visible_chunks {
+                let origin = Self::chunk_origin(*coords);
+                Self::queue_layer(arena, origin, 1.0, &self.meshes[coords], layer);
+            }
+            for tile in &self.visible_lod_tiles {
+                let scale = tile.scale as f32;
+                for slice in &self.lod.mesh(tile).unwrap().slices {
+                    let origin = Self::lod_origin(tile, slice.base_y);
+                    Self::queue_layer(arena, origin, scale, &slice.mesh, layer);
+                }
+            }
+            arena.draw_queued();
+        }
+    }
+
+    // 반투명한 면 - 다른 것을 모두 그린 뒤, 먼 청크부터 깊이 버퍼에 쓰지 않고 섞어서 그린다
+    pub fn render_translucent_chunks(&mut self, program: &mut ShaderProgram, camera: &Vec3) {
+        let lod_distance = |tile: &LodTile| {
+            let size = tile.size() as f32;
+            let center = Self::lod_origin(tile, 0) + vec3(size / 2.0, 0.0, size / 2.0);
+            (center - camera).norm_squared()
+        };
+        let mut tiles: Vec<LodTile> = self
+            .visible_lod_tiles
+            .iter()
+            .filter(|tile| {
+                let slices = &self.lod.mesh(tile).unwrap().slices;
+                slices
+                    .iter()
+                    .any(|slice| slice.mesh.layer(RenderLayer::Translucent).is_some())
+            })
+            .copied()
+            .collect();
+        tiles.sort_by(|a, b| lod_distance(b).total_cmp(&lod_distance(a)));
+
+        let size = CHUNK_SIZE as f32;
+        let distance = |&(x, y, z): &(i32, i32, i32)| {
+            let center = vec3(x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5) * size;
+            (center - camera).norm_squared()
+        };
+
+        let mut chunks: Vec<(i32, i32, i32)> = self
+            .visible_chunks
+            .iter()
+            .filter(|coords| {
+                self.meshes[*coords]
+                    .layer(RenderLayer::Translucent)
+                    .is_some()
+            })
+            .copied()
+            .collect();
+        chunks.sort_by(|a, b| distance(b).total_cmp(&distance(a)));
+
+        // 그리기 명령은 넣은 순서대로 그려지므로 한 번에 그려도 먼 것부터 섞인다
+        let arena = self.arena.get_or_insert_with(MeshArena::new);
+        for tile in tiles {
+            let scale = tile.scale as f32;
+            // 한 타일 안에서도 카메라와 높이 차이가 큰 조각부터
+            let slices = &mut self.lod.mesh_mut(&tile).unwrap().slices;
+            let slice_distance = |slice: &LodSlice| (slice.base_y as f32 - camera.y).abs();
+            let mut order: Vec<usize> = (0..slices.len()).collect();
+            order.sort_by(|&a, &b| {
+                slice_distance(&slices[b]).total_cmp(&slice_distance(&slices[a]))
+            });
+
+            for i in order {
+                let slice = &mut slices[i];
+                let origin = Self::lod_origin(&tile, slice.base_y);
+                let local = (camera - origin) / scale;
+                slice
+                    .mesh
+                    .sort_translucent(arena, [local.x, local.y, local.z]);
+
+                Self::queue_layer(arena, origin, scale, &slice.mesh, RenderLayer::Translucent);
+            }
+        }
+        for coords in chunks {
+            let mesh = self.meshes.get_mut(&coords).unwrap();
+            let origin = Self::chunk_origin(coords);
+            let local = camera - origin;
+            mesh.sort_translucent(arena, [local.x, local.y, local.z]);
+
+            Self::queue_layer(arena, origin, 1.0, mesh, RenderLayer::Translucent);
+        }
+
+        program.set_uniform_matrix4fv("model", Matrix4::<f32>::identity().as_ptr());
+        gl_call!(gl::DepthMask(gl::FALSE));
+        arena.draw_queued();
+        gl_call!(gl::DepthMask(gl::TRUE));
+    }
+
+    // 떨어지는 블록과 아이템 - 매 프레임 메시를 새로 만든다
+    pub fn render_entities(&mut self, program: &mut ShaderProgram, uv_map: &UVMap) {
+        let instances: Vec<EntityInstance> = self
+            .entities
+            .instances()
+            .into_iter()
+            .filter(|instance| uv_map.contains_key(&instance.block))
+            .take(MAX_QUADS_PER_MESH / 6)
+            .collect();
+        if instances.is_empty() {
+            return;
+        }
+
+        let mut vertices = Vec::new();
+        for instance in &instances {
+            let light = self
+                .get_light(
+                    instance.mins.x.floor() as i32,
+                    (instance.mins.y + 0.5).floor() as i32,
+                    instance.mins.z.floor() as i32,
+                )
+                .unwrap_or(lighting::pack_light(MAX_LIGHT, 0));
+            build_block_mesh(&mut vertices, instance.block, uv_map, light);
+        }
+
+        let mesh = self.entity_mesh.get_or_insert_with(ChunkMesh::new);
+        mesh.upload(&vertices);
 
-            gl_call!(gl::BindVertexArray(chunk.vao));
-            program.set_uniform_matrix4fv("model", model_matrix.as_ptr());
-            gl_call!(gl::DrawArrays(
-                gl::TRIANGLES,
-                0,
-                chunk.vertices_drawn as i32
-            ));
+        for (i, instance) in instances.iter().enumerate() {
+            let model = Self::model_matrix(instance.mins, instance.size);
+            program.set_uniform_matrix4fv("model", model.as_ptr());
+            mesh.draw_quads(i * 6, 6);
         }
     }
 }
@@ -0,0 +1,413 @@
+use crate::arena::{AllocationId, ArenaAllocator};
+use crate::constants::{MESH_ARENA_INITIAL_QUADS, TRANSLUCENT_RESORT_DISTANCE};
+use crate::gl_call;
+use crate::mesher::{
+    sort_back_to_front, MeshLayers, RenderLayer, VERTICES_PER_QUAD, WORDS_PER_VERTEX,
+};
+
+// 메시 하나에 들어갈 수 있는 가장 많은 사각형 수 - 계단으로 꽉 찬 청크도 들어간다
+pub const MAX_QUADS_PER_MESH: usize = 1 << 16;
+const INDICES_PER_QUAD: usize = 6;
+const BYTES_PER_QUAD: usize = WORDS_PER_VERTEX * VERTICES_PER_QUAD * std::mem::size_of::<u32>();
+
+lazy_static! {
+    // 모든 메시가 함께 쓰는 index buffer - 사각형 q는 vertex 4q..4q+3을 삼각형 두 개로 나눈다
+    static ref QUAD_INDEX_BUFFER: u32 = create_quad_index_buffer();
+}
+
+fn create_quad_index_buffer() -> u32 {
+    let indices: Vec<u32> = (0..MAX_QUADS_PER_MESH as u32)
+        .flat_map(|quad| [0, 1, 2, 2, 3, 0].map(|corner| quad * VERTICES_PER_QUAD as u32 + corner))
+        .collect();
+
+    let mut ebo = 0;
+    gl_call!(gl::CreateBuffers(1, &mut ebo));
+    gl_call!(gl::NamedBufferStorage(
+        ebo,
+        (indices.len() * std::mem::size_of::<u32>()) as isize,
+        indices.as_ptr() as *const std::os::raw::c_void,
+        0
+    ));
+    ebo
+}
+
+fn quad_count(vertices: &[u32]) -> usize {
+    let quads = vertices.len() / (WORDS_PER_VERTEX * VERTICES_PER_QUAD);
+    assert!(
+        quads <= MAX_QUADS_PER_MESH,
+        "Too many quads in a mesh: {quads}"
+    );
+    quads
+}
+
+// packed vertex를 binding 0에서 읽는 VAO (mesher의 WORDS_PER_VERTEX 참고)
+fn create_vao(vbo: u32) -> u32 {
+    let mut vao = 0;
+    gl_call!(gl::CreateVertexArrays(1, &mut vao));
+
+    gl_call!(gl::EnableVertexArrayAttrib(vao, 0));
+    gl_call!(gl::VertexArrayAttribIFormat(
+        vao,
+        0,
+        WORDS_PER_VERTEX as i32,
+        gl::UNSIGNED_INT,
+        0
+    ));
+    gl_call!(gl::VertexArrayAttribBinding(vao, 0, 0));
+
+    gl_call!(gl::VertexArrayVertexBuffer(
+        vao,
+        0,
+        vbo,
+        0,
+        (WORDS_PER_VERTEX * std::mem::size_of::<u32>()) as i32
+    ));
+    gl_call!(gl::VertexArrayElementBuffer(vao, *QUAD_INDEX_BUFFER));
+
+    vao
+}
+
+fn create_vao_vbo() -> (u32, u32) {
+    let mut vbo = 0;
+    gl_call!(gl::CreateBuffers(1, &mut vbo));
+
+    (create_vao(vbo), vbo)
+}
+
+// VAO와 VBO를 따로 가진 메시 (엔티티) - 메인 스레드(GL context)에서만 만들고 사용한다
+pub struct ChunkMesh {
+    pub vao: u32,
+    pub vbo: u32,
+    pub quads: u32, // 그려지는 사각형의 수
+}
+
+impl ChunkMesh {
+    pub fn new() -> Self {
+        let (vao, vbo) = create_vao_vbo();
+
+        Self { vao, vbo, quads: 0 }
+    }
+
+    pub fn upload(&mut self, vertices: &[u32]) {
+        self.quads = quad_count(vertices) as u32;
+
+        if vertices.is_empty() {
+            return;
+        }
+
+        gl_call!(gl::NamedBufferData(
+            self.vbo,
+            (vertices.len() * std::mem::size_of::<u32>()) as isize,
+            vertices.as_ptr() as *const std::os::raw::c_void,
+            gl::DYNAMIC_DRAW
+        ));
+    }
+
+    pub fn draw(&self) {
+        self.draw_quads(0, self.quads as usize);
+    }
+
+    // first번째 사각형부터 count개만 그린다
+    pub fn draw_quads(&self, first: usize, count: usize) {
+        gl_call!(gl::BindVertexArray(self.vao));
+        gl_call!(gl::DrawElements(
+            gl::TRIANGLES,
+            (count * INDICES_PER_QUAD) as i32,
+            gl::UNSIGNED_INT,
+            (first * INDICES_PER_QUAD * std::mem::size_of::<u32>()) as *const std::os::raw::c_void
+        ));
+    }
+}
+
+impl Drop for ChunkMesh {
+    fn drop(&mut self) {
+        gl_call!(gl::DeleteBuffers(1, &self.vbo));
+        gl_call!(gl::DeleteVertexArrays(1, &self.vao));
+    }
+}
+
+// glMultiDrawElementsIndirect가 읽는 그리기 명령 하나 (GL이 정한 배치)
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct DrawElementsIndirectCommand {
+    count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    base_instance: u32,
+}
+
+// 모든 청크와 LOD 타일의 메시를 담는 GPU 버퍼 하나 - 메시마다 ArenaAllocator로 구간을 나눠 준다
+pub struct MeshArena {
+    allocator: ArenaAllocator,
+    vao: u32,
+    vbo: u32,
+    // 그리기마다 (origin.x, origin.y, origin.z, scale)
+    draw_offset_buffer: u32,
+    command_buffer: u32,
+    commands: Vec<DrawElementsIndirectCommand>,
+    draw_offsets: Vec<[f32; 4]>,
+}
+
+impl MeshArena {
+    pub fn new() -> Self {
+        let vbo = MeshArena::create_vertex_buffer(MESH_ARENA_INITIAL_QUADS);
+        let vao = create_vao(vbo);
+
+        let mut draw_offset_buffer = 0;
+        gl_call!(gl::CreateBuffers(1, &mut draw_offset_buffer));
+        gl_call!(gl::EnableVertexArrayAttrib(vao, 1));
+        gl_call!(gl::VertexArrayAttribFormat(
+            vao,
+            1,
+            4,
+            gl::FLOAT,
+            gl::FALSE,
+            0
+        ));
+        gl_call!(gl::VertexArrayAttribBinding(vao, 1, 1));
+        gl_call!(gl::VertexArrayBindingDivisor(vao, 1, 1));
+        gl_call!(gl::VertexArrayVertexBuffer(
+            vao,
+            1,
+            draw_offset_buffer,
+            0,
+            std::mem::size_of::<[f32; 4]>() as i32
+        ));
+
+        let mut command_buffer = 0;
+        gl_call!(gl::CreateBuffers(1, &mut command_buffer));
+
+        Self {
+            allocator: ArenaAllocator::new(MESH_ARENA_INITIAL_QUADS),
+            vao,
+            vbo,
+            draw_offset_buffer,
+            command_buffer,
+            commands: Vec::new(),
+            draw_offsets: Vec::new(),
+        }
+    }
+
+    fn create_vertex_buffer(quads: usize) -> u32 {
+        let mut vbo = 0;
+        gl_call!(gl::CreateBuffers(1, &mut vbo));
+        gl_call!(gl::NamedBufferStorage(
+            vbo,
+            (quads * BYTES_PER_QUAD) as isize,
+            std::ptr::null(),
+            gl::DYNAMIC_STORAGE_BIT
+        ));
+        vbo
+    }
+
+    pub fn allocator(&self) -> &ArenaAllocator {
+        &self.allocator
+    }
+
+    pub fn quads(&self, id: AllocationId) -> usize {
+        self.allocator.range(id).map_or(0, |range| range.len())
+    }
+
+    // 메시를 버퍼에 올리고 그 자리를 돌려준다 - 자리가 없으면 압축한 더 큰 버퍼로 옮긴다
+    pub fn store(&mut self, vertices: &[u32]) -> AllocationId {
+        let quads = quad_count(vertices);
+        assert!(quads > 0, "Stored an empty mesh");
+
+        let id = match self.allocator.allocate(quads) {
+            Some(id) => id,
+            None => {
+                let mut capacity = self.allocator.capacity();
+                while capacity - self.allocator.used() < quads {
+                    capacity *= 2;
+                }
+                self.relocate(capacity);
+                self.allocator.allocate(quads).unwrap()
+            }
+        };
+        self.write(id, vertices);
+        id
+    }
+
+    // 같은 크기의 메시로 덮어쓴다 (반투명한 면을 다시 정렬했을 때)
+    pub fn rewrite(&mut self, id: AllocationId, vertices: &[u32]) {
+        assert_eq!(
+            self.quads(id),
+            quad_count(vertices),
+            "Rewrote a mesh with another size"
+        );
+        self.write(id, vertices);
+    }
+
+    fn write(&self, id: AllocationId, vertices: &[u32]) {
+        let start = self.allocator.range(id).unwrap().start;
+        gl_call!(gl::NamedBufferSubData(
+            self.vbo,
+            (start * BYTES_PER_QUAD) as isize,
+            (vertices.len() * std::mem::size_of::<u32>()) as isize,
+            vertices.as_ptr() as *const std::os::raw::c_void
+        ));
+    }
+
+    pub fn release(&mut self, id: AllocationId) {
+        self.allocator.free(id);
+    }
+
+    // 새 버퍼를 만들어 할당들을 앞에서부터 빈틈없이 옮겨 담는다
+    fn relocate(&mut self, capacity: usize) {
+        let vbo = MeshArena::create_vertex_buffer(capacity);
+        for relocation in self.allocator.compact() {
+            gl_call!(gl::CopyNamedBufferSubData(
+                self.vbo,
+                vbo,
+                (relocation.from * BYTES_PER_QUAD) as isize,
+                (relocation.to * BYTES_PER_QUAD) as isize,
+                (relocation.size * BYTES_PER_QUAD) as isize
+            ));
+        }
+        self.allocator.grow(capacity);
+
+        gl_call!(gl::VertexArrayVertexBuffer(
+            self.vao,
+            0,
+            vbo,
+            0,
+            (WORDS_PER_VERTEX * std::mem::size_of::<u32>()) as i32
+        ));
+        gl_call!(gl::DeleteBuffers(1, &self.vbo));
+        self.vbo = vbo;
+    }
+
+    // 다음 draw_queued에서 그릴 메시 - 메시 안 좌표에 scale을 곱하고 origin을 더한 곳에 그린다
+    pub fn queue(&mut self, id: AllocationId, origin: [f32; 3], scale: f32) {
+        let range = self.allocator.range(id).unwrap();
+        self.commands.push(DrawElementsIndirectCommand {
+            count: (range.len() * INDICES_PER_QUAD) as u32,
+            instance_count: 1,
+            first_index: 0,
+            base_vertex: (range.start * VERTICES_PER_QUAD) as i32,
+            base_instance: self.draw_offsets.len() as u32,
+        });
+        self.draw_offsets
+            .push([origin[0], origin[1], origin[2], scale]);
+    }
+
+    // 쌓인 메시를 쌓인 순서대로 한 번에 그리고 비운다
+    pub fn draw_queued(&mut self) {
+        if self.commands.is_empty() {
+            return;
+        }
+
+        gl_call!(gl::NamedBufferData(
+            self.draw_offset_buffer,
+            (self.draw_offsets.len() * std::mem::size_of::<[f32; 4]>()) as isize,
+            self.draw_offsets.as_ptr() as *const std::os::raw::c_void,
+            gl::STREAM_DRAW
+        ));
+        gl_call!(gl::NamedBufferData(
+            self.command_buffer,
+            (self.commands.len() * std::mem::size_of::<DrawElementsIndirectCommand>()) as isize,
+            self.commands.as_ptr() as *const std::os::raw::c_void,
+            gl::STREAM_DRAW
+        ));
+
+        gl_call!(gl::BindVertexArray(self.vao));
+        gl_call!(gl::BindBuffer(
+            gl::DRAW_INDIRECT_BUFFER,
+            self.command_buffer
+        ));
+        gl_call!(gl::MultiDrawElementsIndirect(
+            gl::TRIANGLES,
+            gl::UNSIGNED_INT,
+            std::ptr::null(),
+            self.commands.len() as i32,
+            0
+        ));
+        gl_call!(gl::BindBuffer(gl::DRAW_INDIRECT_BUFFER, 0));
+
+        self.commands.clear();
+        self.draw_offsets.clear();
+    }
+}
+
+impl Drop for MeshArena {
+    fn drop(&mut self) {
+        let buffers = [self.vbo, self.draw_offset_buffer, self.command_buffer];
+        gl_call!(gl::DeleteBuffers(buffers.len() as i32, buffers.as_ptr()));
+        gl_call!(gl::DeleteVertexArrays(1, &self.vao));
+    }
+}
+
+// 청크 하나의 렌더 단계별 메시 - 버리기 전에 release로 arena의 자리를 돌려줘야 한다
+pub struct LayeredChunkMesh {
+    layers: [Option<AllocationId>; 3],
+    translucent: Vec<u32>,
+    // 마지막으로 정렬했을 때의 카메라 위치 (메시 안 좌표)
+    sorted_from: Option<[f32; 3]>,
+}
+
+impl LayeredChunkMesh {
+    pub fn new() -> Self {
+        Self {
+            layers: [None, None, None],
+            translucent: Vec::new(),
+            sorted_from: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.iter().all(|allocation| allocation.is_none())
+    }
+
+    // 크기가 같은 단계는 자리를 그대로 쓰고, 다른 단계는 자리를 새로 받는다
+    pub fn upload(&mut self, arena: &mut MeshArena, layers: MeshLayers) {
+        for (layer, vertices) in layers.into_iter().enumerate() {
+            match self.layers[layer] {
+                Some(id) if arena.quads(id) == quad_count(&vertices) => {
+                    arena.rewrite(id, &vertices)
+                }
+                previous => {
+                    if let Some(id) = previous {
+                        arena.release(id);
+                    }
+                    self.layers[layer] = (!vertices.is_empty()).then(|| arena.store(&vertices));
+                }
+            }
+
+            if layer == RenderLayer::Translucent as usize {
+                self.translucent = vertices;
+                self.sorted_from = None;
+            }
+        }
+    }
+
+    pub fn release(&mut self, arena: &mut MeshArena) {
+        for id in self.layers.iter_mut().filter_map(Option::take) {
+            arena.release(id);
+        }
+        self.translucent.clear();
+    }
+
+    pub fn layer(&self, layer: RenderLayer) -> Option<AllocationId> {
+        self.layers[layer as usize]
+    }
+
+    // 카메라가 마지막 정렬 위치에서 충분히 멀어졌으면 반투명 면을 다시 정렬해서 올린다
+    pub fn sort_translucent(&mut self, arena: &mut MeshArena, camera: [f32; 3]) {
+        if self.translucent.is_empty() {
+            return;
+        }
+        if let Some(from) = self.sorted_from {
+            let moved: f32 = (0..3).map(|axis| (camera[axis] - from[axis]).powi(2)).sum();
+            if moved < TRANSLUCENT_RESORT_DISTANCE * TRANSLUCENT_RESORT_DISTANCE {
+                return;
+            }
+        }
+
+        sort_back_to_front(&mut self.translucent, camera);
+        if let Some(id) = self.layers[RenderLayer::Translucent as usize] {
+            arena.rewrite(id, &self.translucent);
+        }
+        self.sorted_from = Some(camera);
+    }
+}
@@ -8,18 +8,84 @@ pub const BACKGROUND_COLOR: (f32, f32, f32, f32) = (0.74, 0.84, 1.0, 1.0);
 
 pub const NEAR_PLANE: f32 = 0.1;
 pub const FAR_PLANE: f32 = 1000.0;
+// 카메라가 이만큼 움직이면 청크의 반투명 면을 다시 정렬한다
+pub const TRANSLUCENT_RESORT_DISTANCE: f32 = 1.0;
+// 그린 청크 수와 시야 밖이라 건너뛴 청크 수를 창 제목에 갱신하는 간격 (초)
+pub const RENDER_STATS_INTERVAL: f32 = 1.0;
+// 청크 메시를 모두 담는 버퍼의 처음 크기 (사각형 수) - 모자라면 두 배씩 늘린다
+pub const MESH_ARENA_INITIAL_QUADS: usize = 1 << 18;
 // input
 pub const MOUSE_SENSITIVITY_X: f32 = 1.0;
 pub const MOUSE_SENSITIVITY_Y: f32 = 1.0;
+// 숫자 키 1~9로 고르는 놓을 블록
+pub const HOTBAR: [&str; 9] = [
+    "debug2",
+    "stone",
+    "oak_log",
+    "stone_slab",
+    "cobblestone_stairs",
+    "snow",
+    "sand",
+    "water",
+    "tnt",
+];
 
 // Physics
 pub const PHYSICS_TICKRATE: f32 = 60.0;
 pub const GRAVITY: f32 = -28.0;
 pub const MAX_VERTICAL_VELOCITY: f32 = 90.0;
 
+// Entities
+pub const FALLING_BLOCK_HALF_WIDTH: f32 = 0.49;
+pub const ITEM_SIZE: f32 = 0.25;
+// 아이템이 사라지기까지 걸리는 시간 (초)
+pub const ITEM_DESPAWN_TIME: f32 = 300.0;
+// 이 높이보다 아래로 떨어진 엔티티는 지운다
+pub const VOID_DEPTH: f32 = -64.0;
+// 폭발에 휘말린 TNT가 터지기까지 걸리는 시간의 범위 (초)
+pub const CHAINED_FUSE_MIN: f32 = 0.5;
+pub const CHAINED_FUSE_MAX: f32 = 1.5;
+// 불이 붙은 TNT가 튀어 오르는 속도
+pub const PRIMED_TNT_JUMP: f32 = 4.0;
+// 폭발의 한가운데에서 받는 속도 - 멀어질수록 줄어든다
+pub const EXPLOSION_KNOCKBACK: f32 = 20.0;
+
+// Blocks
+pub const BLOCK_REGISTRY_PATH: &str = "blocks/blocks.json";
+
+// World
+pub const WORLD_DIRECTORY: &str = "world";
+pub const WORLD_HEIGHT_CHUNKS: i32 = 16;
+pub const RENDER_DISTANCE: i32 = 10;
+pub const MAX_COLUMNS_GENERATED_PER_FRAME: usize = 4;
+pub const MAX_CHUNKS_REBUILT_PER_FRAME: usize = 32;
+// 구조물 블록을 기억해 두는 기둥 수 - 이웃 기둥을 꾸밀 때 다시 쓴다
+pub const FEATURE_CACHE_COLUMNS: usize = 256;
+// 불러온 청크 밖의 지형은 칸 하나가 2, 4, 8블록인 LOD 메시로 그린다
+// LOD_DISTANCES[i]는 LOD_SCALES[i]로 그리는 가장 먼 거리 (청크)
+pub const LOD_SCALES: [i32; 3] = [2, 4, 8];
+pub const LOD_DISTANCES: [i32; 3] = [16, 32, 64];
+// LOD 타일 가장자리의 벽을 칸 몇 개만큼 더 내려서 다른 단계와의 틈을 가린다
+pub const LOD_SKIRT_DEPTH: i32 = 2;
+pub const MAX_LOD_TILES_MESHED_PER_FRAME: usize = 4;
+// 물리 틱 몇 번마다 월드 틱이 한 번 지나는지 (60 / 3 = 초당 20틱)
+pub const PHYSICS_TICKS_PER_WORLD_TICK: u64 = 3;
+pub const RANDOM_TICK_SPEED: u32 = 3;
+pub const MAX_SCHEDULED_TICKS_PER_TICK: usize = 1024;
+// 프레임마다 빛을 지우거나 퍼뜨리는 블록 수
+pub const MAX_LIGHT_UPDATES_PER_FRAME: usize = 20000;
+
 // Texture
-pub const TEXTURE_ATLAS_SIZE: u32 = 1024;
-pub const BLOCK_TEXTURE_SIZE: u32 = 16;
+// 아틀라스가 커질 수 있는 한 변의 최대 크기 (픽셀)
+pub const TEXTURE_ATLAS_MAX_SIZE: u32 = 4096;
+// 타일 둘레에 가장자리 픽셀을 늘여 채우는 폭 - 먼 곳의 mipmap에서 옆 타일 색이 번지지 않게 한다
+pub const TEXTURE_ATLAS_PADDING: u32 = 4;
+// 16x16 타일이 1x1이 될 때까지
+pub const TEXTURE_ATLAS_MIP_LEVELS: u32 = 5;
+// 켜면 시작할 때 아틀라스를 확인용으로 저장한다
+pub const EXPORT_TEXTURE_ATLAS: bool = false;
+pub const TEXTURE_ATLAS_EXPORT_PNG: &str = "atlas.png";
+pub const TEXTURE_ATLAS_EXPORT_JSON: &str = "atlas.json";
 
 // Player
 pub const REACH_DISTANCE: f32 = 400.0;
@@ -1,3 +1,4 @@
+use crate::chunk::BlockID;
 use specs::{Component, VecStorage};
 
 #[derive(Debug)]
@@ -8,8 +9,53 @@ impl Component for Position {
 }
 
 #[derive(Debug)]
-pub struct Velocity(pub f32, pub f32);
+pub struct Velocity(pub f32, pub f32, pub f32);
 
 impl Component for Velocity {
     type Storage = VecStorage<Self>;
 }
+
+// 블록과 충돌하는 상자 - Position은 상자 밑면의 가운데
+#[derive(Debug)]
+pub struct Collider {
+    pub half_width: f32,
+    pub height: f32,
+    pub on_ground: bool,
+}
+
+impl Component for Collider {
+    type Storage = VecStorage<Self>;
+}
+
+// 중력 때문에 떨어지는 블록 - 땅에 닿으면 다시 블록이 된다
+#[derive(Debug)]
+pub struct FallingBlock(pub BlockID);
+
+impl Component for FallingBlock {
+    type Storage = VecStorage<Self>;
+}
+
+// 블록을 놓을 수 없을 때 대신 떨어지는 아이템
+#[derive(Debug)]
+pub struct DroppedItem {
+    pub block: BlockID,
+    // 생긴 지 몇 초가 지났는지
+    pub age: f32,
+}
+
+impl Component for DroppedItem {
+    type Storage = VecStorage<Self>;
+}
+
+// 불이 붙은 TNT - 도화선이 다 타면 터진다
+#[derive(Debug)]
+pub struct PrimedTnt {
+    pub block: BlockID,
+    // 남은 시간 (초)
+    pub fuse: f32,
+    pub power: f32,
+}
+
+impl Component for PrimedTnt {
+    type Storage = VecStorage<Self>;
+}
@@ -23,6 +23,7 @@ impl<'a> System<'a> for Physics {
             // Generate Iterator
             pos.0 += vel.0 * dt;
             pos.1 += vel.1 * dt;
+            pos.2 += vel.2 * dt;
         }
     }
 }
@@ -0,0 +1,298 @@
+use crate::aabb::AABB;
+use crate::chunk::BlockID;
+use crate::chunk_manager::ChunkManager;
+use crate::constants::{
+    FALLING_BLOCK_HALF_WIDTH, FRICTION, GRAVITY, ITEM_DESPAWN_TIME, ITEM_SIZE,
+    MAX_VERTICAL_VELOCITY, PRIMED_TNT_JUMP, VOID_DEPTH,
+};
+use crate::ecs::components::{Collider, DroppedItem, FallingBlock, Position, PrimedTnt, Velocity};
+use crate::explosion::{self, Explosion};
+use crate::physics::move_and_collide;
+use nalgebra_glm::{vec3, Vec3};
+use rand::random;
+use specs::prelude::*;
+
+// 한 물리 틱 동안 생긴 일 중 월드를 바꿔야 하는 것
+pub enum EntityEvent {
+    // 떨어지던 블록이 이 위치에 멈췄다
+    BlockLanded {
+        x: i32,
+        y: i32,
+        z: i32,
+        block: BlockID,
+    },
+    // 불이 붙은 TNT가 터졌다
+    Exploded(Explosion),
+}
+
+// 그리기 위한 엔티티 하나의 상자와 블록
+pub struct EntityInstance {
+    pub mins: Vec3,
+    pub size: f32,
+    pub block: BlockID,
+}
+
+// 플레이어 말고 움직이는 것들 (떨어지는 블록, 아이템, 불이 붙은 TNT)
+pub struct EntityManager {
+    world: World,
+}
+
+impl EntityManager {
+    pub fn new() -> Self {
+        let mut world = World::new();
+        world.register::<Position>();
+        world.register::<Velocity>();
+        world.register::<Collider>();
+        world.register::<FallingBlock>();
+        world.register::<DroppedItem>();
+        world.register::<PrimedTnt>();
+
+        Self { world }
+    }
+
+    // 블록 (x, y, z) 자리에서 떨어지기 시작하는 블록
+    pub fn spawn_falling_block(&mut self, x: i32, y: i32, z: i32, block: BlockID) {
+        self.world
+            .create_entity()
+            .with(Position(x as f32 + 0.5, y as f32, z as f32 + 0.5))
+            .with(Velocity(0.0, 0.0, 0.0))
+            .with(Collider {
+                half_width: FALLING_BLOCK_HALF_WIDTH,
+                height: FALLING_BLOCK_HALF_WIDTH * 2.0,
+                on_ground: false,
+            })
+            .with(FallingBlock(block))
+            .build();
+    }
+
+    // 블록 (x, y, z)의 가운데에 떨어지는 아이템
+    pub fn spawn_item(&mut self, x: i32, y: i32, z: i32, block: BlockID) {
+        self.world
+            .create_entity()
+            .with(Position(x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5))
+            .with(Velocity(0.0, 0.0, 0.0))
+            .with(Collider {
+                half_width: ITEM_SIZE / 2.0,
+                height: ITEM_SIZE,
+                on_ground: false,
+            })
+            .with(DroppedItem { block, age: 0.0 })
+            .build();
+    }
+
+    // 블록 (x, y, z) 자리에서 불이 붙은 TNT - 살짝 튀어 오른다
+    pub fn spawn_primed_tnt(
+        &mut self,
+        x: i32,
+        y: i32,
+        z: i32,
+        block: BlockID,
+        fuse: f32,
+        power: f32,
+    ) {
+        let angle = random::<f32>() * std::f32::consts::TAU;
+
+        self.world
+            .create_entity()
+            .with(Position(x as f32 + 0.5, y as f32, z as f32 + 0.5))
+            .with(Velocity(
+                angle.cos() * PRIMED_TNT_JUMP * 0.1,
+                PRIMED_TNT_JUMP,
+                angle.sin() * PRIMED_TNT_JUMP * 0.1,
+            ))
+            .with(Collider {
+                half_width: FALLING_BLOCK_HALF_WIDTH,
+                height: FALLING_BLOCK_HALF_WIDTH * 2.0,
+                on_ground: false,
+            })
+            .with(PrimedTnt { block, fuse, power })
+            .build();
+    }
+
+    // 폭발 근처의 엔티티를 밀어낸다
+    pub fn apply_explosion(&mut self, explosion: &Explosion) {
+        let positions = self.world.read_storage::<Position>();
+        let colliders = self.world.read_storage::<Collider>();
+        let mut velocities = self.world.write_storage::<Velocity>();
+
+        for (position, collider, velocity) in (&positions, &colliders, &mut velocities).join() {
+            let center = vec3(position.0, position.1 + collider.height / 2.0, position.2);
+            let push = explosion::knockback(explosion, &center);
+
+            velocity.0 += push.x;
+            velocity.1 += push.y;
+            velocity.2 += push.z;
+        }
+    }
+
+    // 엔티티를 dt초만큼 움직인다 - 불러오지 않은 청크 기둥 위의 엔티티는 멈춰 둔다
+    pub fn step(
+        &mut self,
+        dt: f32,
+        get_block: &dyn Fn(i32, i32, i32) -> Option<BlockID>,
+        is_column_loaded: &dyn Fn(i32, i32) -> bool,
+    ) -> Vec<EntityEvent> {
+        let collision_shape =
+            |x, y, z| get_block(x, y, z).map(|block: BlockID| block.definition().collision_shape());
+        let mut events = Vec::new();
+
+        {
+            let entities = self.world.entities();
+            let mut positions = self.world.write_storage::<Position>();
+            let mut velocities = self.world.write_storage::<Velocity>();
+            let mut colliders = self.world.write_storage::<Collider>();
+            let falling_blocks = self.world.read_storage::<FallingBlock>();
+            let mut items = self.world.write_storage::<DroppedItem>();
+            let mut primed = self.world.write_storage::<PrimedTnt>();
+
+            for (entity, position, velocity, collider) in
+                (&entities, &mut positions, &mut velocities, &mut colliders).join()
+            {
+                let (chunk_x, _, chunk_z) =
+                    ChunkManager::get_chunk_coords(position.0, position.1, position.2);
+                if !is_column_loaded(chunk_x, chunk_z) {
+                    continue;
+                }
+                if position.1 < VOID_DEPTH {
+                    let _ = entities.delete(entity);
+                    continue;
+                }
+
+                let mut v = vec3(velocity.0, velocity.1, velocity.2);
+                v.y = (v.y + GRAVITY * dt).max(-MAX_VERTICAL_VELOCITY);
+                if collider.on_ground {
+                    v.x *= (1.0 - FRICTION * dt).max(0.0);
+                    v.z *= (1.0 - FRICTION * dt).max(0.0);
+                }
+
+                let mut aabb = AABB::new(
+                    vec3(
+                        position.0 - collider.half_width,
+                        position.1,
+                        position.2 - collider.half_width,
+                    ),
+                    vec3(
+                        position.0 + collider.half_width,
+                        position.1 + collider.height,
+                        position.2 + collider.half_width,
+                    ),
+                );
+                collider.on_ground = move_and_collide(&mut aabb, &mut v, dt, &collision_shape);
+
+                *position = Position(
+                    (aabb.mins.x + aabb.maxs.x) / 2.0,
+                    aabb.mins.y,
+                    (aabb.mins.z + aabb.maxs.z) / 2.0,
+                );
+                *velocity = Velocity(v.x, v.y, v.z);
+
+                if let Some(&FallingBlock(block)) = falling_blocks.get(entity) {
+                    if collider.on_ground {
+                        // 밑면이 닿은 블록 칸 - 반 블록 위에 멈추면 그 반 블록 자리가 된다
+                        events.push(EntityEvent::BlockLanded {
+                            x: position.0.floor() as i32,
+                            y: (position.1 + 1e-3).floor() as i32,
+                            z: position.2.floor() as i32,
+                            block,
+                        });
+                        let _ = entities.delete(entity);
+                    }
+                }
+
+                if let Some(tnt) = primed.get_mut(entity) {
+                    tnt.fuse -= dt;
+                    if tnt.fuse <= 0.0 {
+                        events.push(EntityEvent::Exploded(Explosion {
+                            center: vec3(
+                                position.0,
+                                position.1 + collider.height / 2.0,
+                                position.2,
+                            ),
+                            power: tnt.power,
+                        }));
+                        let _ = entities.delete(entity);
+                    }
+                }
+
+                if let Some(item) = items.get_mut(entity) {
+                    item.age += dt;
+                    if item.age > ITEM_DESPAWN_TIME {
+                        let _ = entities.delete(entity);
+                    }
+                }
+            }
+        }
+
+        self.world.maintain();
+        events
+    }
+
+    pub fn instances(&self) -> Vec<EntityInstance> {
+        let entities = self.world.entities();
+        let positions = self.world.read_storage::<Position>();
+        let colliders = self.world.read_storage::<Collider>();
+        let falling_blocks = self.world.read_storage::<FallingBlock>();
+        let items = self.world.read_storage::<DroppedItem>();
+        let primed = self.world.read_storage::<PrimedTnt>();
+
+        (&entities, &positions, &colliders)
+            .join()
+            .filter_map(|(entity, position, collider)| {
+                let block = if let Some(falling) = falling_blocks.get(entity) {
+                    falling.0
+                } else if let Some(item) = items.get(entity) {
+                    item.block
+                } else {
+                    primed.get(entity)?.block
+                };
+
+                Some(EntityInstance {
+                    mins: vec3(
+                        position.0 - collider.half_width,
+                        position.1,
+                        position.2 - collider.half_width,
+                    ),
+                    size: collider.height,
+                    block,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_registry::BLOCKS;
+
+    fn landing_height(floor: BlockID) -> i32 {
+        let mut entities = EntityManager::new();
+        entities.spawn_falling_block(0, 5, 0, BLOCKS.id("sand"));
+        let get_block = |_, y, _| {
+            Some(if y == 1 {
+                floor
+            } else if y < 1 {
+                BLOCKS.id("stone")
+            } else {
+                BlockID::AIR
+            })
+        };
+
+        for _ in 0..200 {
+            for event in entities.step(1.0 / 60.0, &get_block, &|_, _| true) {
+                if let EntityEvent::BlockLanded { x, y, z, .. } = event {
+                    assert_eq!((x, z), (0, 0));
+                    return y;
+                }
+            }
+        }
+        panic!("the falling block never landed");
+    }
+
+    #[test]
+    fn falling_block_lands_in_the_cell_it_rests_in() {
+        assert_eq!(landing_height(BlockID::AIR), 1);
+        assert_eq!(landing_height(BLOCKS.id("stone")), 2);
+        assert_eq!(landing_height(BLOCKS.id("stone_slab")), 1);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::chunk::BlockID;
+use crate::chunk_manager::ChunkManager;
+use crate::constants::{CHAINED_FUSE_MAX, CHAINED_FUSE_MIN, EXPLOSION_KNOCKBACK};
+use nalgebra_glm::{vec3, Vec3};
+use rand::Rng;
+use std::collections::HashSet;
+
+// 한 변에 쏘는 광선 수 - 16x16x16 격자의 겉면에서 바깥으로 쏜다
+const RAYS_PER_SIDE: i32 = 16;
+// 광선이 한 번에 나아가는 거리
+const RAY_STEP: f32 = 0.3;
+// 광선은 나아갈 때마다 이만큼 약해지고, 블록을 지날 때는 (견디는 정도 + 0.3) * RAY_STEP만큼 더 약해진다
+const RAY_FALLOFF: f32 = 0.75 * RAY_STEP;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Explosion {
+    pub center: Vec3,
+    pub power: f32,
+}
+
+// 폭발이 (target)에 있는 것에 주는 속도 - 세기의 두 배 거리 밖에서는 0
+pub fn knockback(explosion: &Explosion, target: &Vec3) -> Vec3 {
+    let radius = explosion.power * 2.0;
+    let offset = target - explosion.center;
+    let distance = offset.norm();
+
+    if distance >= radius {
+        return Vec3::zeros();
+    }
+
+    let direction = offset.try_normalize(1.0e-6).unwrap_or_else(Vec3::y);
+    direction * (1.0 - distance / radius) * EXPLOSION_KNOCKBACK
+}
+
+// 블록 (x, y, z)가 터지는 블록이면 불을 붙인다 - 불이 붙었으면 true
+pub fn ignite(world: &mut ChunkManager, x: i32, y: i32, z: i32) -> bool {
+    let block = match world.get_block(x, y, z) {
+        Some(block) => block,
+        None => return false,
+    };
+    let explosive = match block.definition().explosive {
+        Some(explosive) => explosive,
+        None => return false,
+    };
+
+    world.set_block(x, y, z, BlockID::AIR);
+    world
+        .entities
+        .spawn_primed_tnt(x, y, z, block, explosive.fuse, explosive.power);
+    true
+}
+
+fn ray_directions() -> Vec<Vec3> {
+    let max = RAYS_PER_SIDE - 1;
+    let mut directions = Vec::new();
+
+    for x in 0..RAYS_PER_SIDE {
+        for y in 0..RAYS_PER_SIDE {
+            for z in 0..RAYS_PER_SIDE {
+                let on_surface = [x, y, z].iter().any(|&c| c == 0 || c == max);
+                if !on_surface {
+                    continue;
+                }
+
+                let direction =
+                    vec3(x as f32, y as f32, z as f32) / max as f32 * 2.0 - vec3(1.0, 1.0, 1.0);
+                directions.push(direction.normalize());
+            }
+        }
+    }
+
+    directions
+}
+
+// 폭발이 부수는 블록 - 광선이 블록을 지날 때마다 그 블록이 견디는 만큼 약해진다
+fn destroyed_blocks(
+    world: &ChunkManager,
+    explosion: &Explosion,
+    rng: &mut impl Rng,
+) -> HashSet<(i32, i32, i32)> {
+    let mut destroyed = HashSet::new();
+
+    for direction in ray_directions() {
+        let mut intensity = explosion.power * rng.gen_range(0.7..1.3);
+        let mut position = explosion.center;
+
+        while intensity > 0.0 {
+            let (x, y, z) = (
+                position.x.floor() as i32,
+                position.y.floor() as i32,
+                position.z.floor() as i32,
+            );
+
+            let block = match world.get_block(x, y, z) {
+                Some(block) => block,
+                // 불러오지 않은 곳이나 월드 밖으로 나간 광선은 멈춘다
+                None => break,
+            };
+
+            if !block.is_air() {
+                intensity -= (block.definition().blast_resistance() + 0.3) * RAY_STEP;
+                if intensity > 0.0 {
+                    destroyed.insert((x, y, z));
+                }
+            }
+
+            position += direction * RAY_STEP;
+            intensity -= RAY_FALLOFF;
+        }
+    }
+
+    destroyed
+}
+
+// 폭발 하나를 처리한다 - 부서진 블록은 한 번에 바꾸고, 휘말린 TNT에는 짧은 도화선으로 불을 붙인다
+pub fn explode(world: &mut ChunkManager, explosion: Explosion, rng: &mut impl Rng) {
+    let destroyed = destroyed_blocks(world, &explosion, rng);
+    let mut edits = Vec::with_capacity(destroyed.len());
+    let mut chained = Vec::new();
+    let mut drops = Vec::new();
+
+    for (x, y, z) in destroyed {
+        let block = world.get_block(x, y, z).unwrap();
+
+        if let Some(explosive) = block.definition().explosive {
+            chained.push(((x, y, z), block, explosive.power));
+        } else if !block.is_fluid() && rng.gen::<f32>() < 1.0 / explosion.power {
+            drops.push(((x, y, z), block));
+        }
+        edits.push(((x, y, z), BlockID::AIR));
+    }
+
+    world.set_blocks(&edits);
+    world.entities.apply_explosion(&explosion);
+
+    for ((x, y, z), block, power) in chained {
+        let fuse = rng.gen_range(CHAINED_FUSE_MIN..CHAINED_FUSE_MAX);
+        world.entities.spawn_primed_tnt(x, y, z, block, fuse, power);
+    }
+    for ((x, y, z), block) in drops {
+        world.entities.spawn_item(x, y, z, block);
+    }
+
+    world.explosions.push(explosion);
+}
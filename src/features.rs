@@ -0,0 +1,576 @@
+use crate::biome::{Biome, BiomeSettings};
+use crate::block_registry::BLOCKS;
+use crate::chunk::BlockID;
+use crate::chunk_manager::CHUNK_SIZE;
+use crate::constants::FEATURE_CACHE_COLUMNS;
+use crate::world_gen::{ColumnAccess, WorldGenerator};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+// 지형 위에 놓이는 구조물 (나무, 바위, 꽃, 광물 덩어리...) - 이웃 기둥까지 넘칠 수 있다
+pub trait Feature: Send + Sync {
+    fn radius(&self) -> i32;
+
+    fn place(&self, origin: (i32, i32, i32), rng: &mut ChaCha8Rng, writer: &mut FeatureWriter);
+}
+
+// 구조물을 어디에 둘지
+#[derive(Copy, Clone, Debug)]
+pub enum Anchor {
+    // 지표면 블록 위 (origin은 지표면 블록)
+    Surface,
+    // 지하의 임의의 높이
+    Underground { min_y: i32, max_y: i32 },
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum Density {
+    Fixed(f64),
+    // 바이옴의 vegetation_density에 곱한 확률
+    Vegetation(f64),
+}
+
+impl Density {
+    fn chance(&self, biome: &BiomeSettings) -> f64 {
+        match *self {
+            Density::Fixed(chance) => chance,
+            Density::Vegetation(factor) => biome.vegetation_density * factor,
+        }
+    }
+}
+
+pub struct Placement {
+    // 청크 기둥마다 위치를 몇 번 골라 보는지
+    pub attempts: u32,
+    pub density: Density,
+    pub anchor: Anchor,
+    // 비어 있으면 모든 바이옴
+    pub biomes: Vec<Biome>,
+    // 지표면 구조물이 설 수 있는 블록 (비어 있으면 모든 블록)
+    pub ground: Vec<BlockID>,
+}
+
+struct RegisteredFeature {
+    name: String,
+    feature: Box<dyn Feature>,
+    placement: Placement,
+}
+
+// 구조물이 블록을 쓸 수 있는 자리 - 블록을 실제로 쓸 때의 월드를 보고 판단한다
+#[derive(Clone, Debug)]
+pub enum Replace {
+    // 공기나 고체가 아닌 블록 (꽃 같은)
+    NonSolid,
+    // 고체가 아닌 블록이나 이 블록 (나무 줄기는 다른 나무의 잎을 뚫고 자란다)
+    NonSolidOr(BlockID),
+    AnyOf(Vec<BlockID>),
+    // 바로 아래가 이 중 하나인 공기 (풀 위의 꽃)
+    AirAbove(Vec<BlockID>),
+}
+
+impl Replace {
+    fn allows(
+        &self,
+        world: &dyn ColumnAccess,
+        (x, y, z): (i32, i32, i32),
+        existing: BlockID,
+    ) -> bool {
+        match self {
+            Replace::NonSolid => !existing.is_solid(),
+            Replace::NonSolidOr(block) => !existing.is_solid() || existing == *block,
+            Replace::AnyOf(blocks) => blocks.contains(&existing),
+            Replace::AirAbove(ground) => {
+                existing.is_air()
+                    && world
+                        .get_block(x, y - 1, z)
+                        .map_or(false, |below| ground.contains(&below))
+            }
+        }
+    }
+}
+
+// 구조물이 쓰려는 블록 하나
+#[derive(Clone, Debug)]
+struct FeatureBlock {
+    position: (i32, i32, i32),
+    block: BlockID,
+    replace: Replace,
+}
+
+impl FeatureBlock {
+    fn column(&self) -> (i32, i32) {
+        let size = CHUNK_SIZE as i32;
+        (
+            self.position.0.div_euclid(size),
+            self.position.2.div_euclid(size),
+        )
+    }
+}
+
+// 구조물이 쓰려는 블록을 순서대로 모은다 - 어느 청크 기둥에 들어가든 상관없다
+pub struct FeatureWriter {
+    blocks: Vec<FeatureBlock>,
+}
+
+impl FeatureWriter {
+    // 공기나 고체가 아닌 블록(꽃 같은)만 덮어쓴다
+    pub fn place(&mut self, x: i32, y: i32, z: i32, block: BlockID) {
+        self.replace_if(x, y, z, block, Replace::NonSolid);
+    }
+
+    pub fn replace_if(&mut self, x: i32, y: i32, z: i32, block: BlockID, replace: Replace) {
+        self.blocks.push(FeatureBlock {
+            position: (x, y, z),
+            block,
+            replace,
+        });
+    }
+}
+
+// 최근에 구조물을 놓아 본 기둥들 - 이웃 기둥을 꾸밀 때 다시 쓰고, 오래된 것부터 버린다
+#[derive(Default)]
+struct PlacementCache {
+    // 기둥 -> (그 기둥에 뿌리를 둔 구조물의 블록, 마지막으로 쓴 때)
+    columns: HashMap<(i32, i32), (Arc<Vec<FeatureBlock>>, u64)>,
+    clock: u64,
+}
+
+impl PlacementCache {
+    fn get(&mut self, column: (i32, i32)) -> Option<Arc<Vec<FeatureBlock>>> {
+        self.clock += 1;
+        let (blocks, last_used) = self.columns.get_mut(&column)?;
+        *last_used = self.clock;
+        Some(blocks.clone())
+    }
+
+    // 가득 차면 가장 오래 쓰지 않은 기둥을 버린다
+    fn insert(&mut self, column: (i32, i32), blocks: Arc<Vec<FeatureBlock>>) {
+        self.clock += 1;
+        self.columns.insert(column, (blocks, self.clock));
+
+        if self.columns.len() > FEATURE_CACHE_COLUMNS {
+            let oldest = self
+                .columns
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(&column, _)| column);
+            if let Some(oldest) = oldest {
+                self.columns.remove(&oldest);
+            }
+        }
+    }
+}
+
+pub struct FeatureRegistry {
+    features: Vec<RegisteredFeature>,
+    placed: Mutex<PlacementCache>,
+}
+
+impl FeatureRegistry {
+    pub fn new() -> Self {
+        Self {
+            features: Vec::new(),
+            placed: Mutex::new(PlacementCache::default()),
+        }
+    }
+
+    pub fn register(&mut self, name: &str, feature: impl Feature + 'static, placement: Placement) {
+        self.features.push(RegisteredFeature {
+            name: name.to_owned(),
+            feature: Box::new(feature),
+            placement,
+        });
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.features.iter().map(|feature| feature.name.as_str())
+    }
+
+    pub fn with_default_features() -> Self {
+        let mut registry = FeatureRegistry::new();
+        let grass = vec![BLOCKS.id("grass_block"), BLOCKS.id("snowy_grass_block")];
+
+        registry.register(
+            "oak_tree",
+            Tree {
+                log: BLOCKS.id("oak_log"),
+                leaves: BLOCKS.id("oak_leaves"),
+                min_height: 4,
+                max_height: 6,
+            },
+            Placement {
+                attempts: CHUNK_SIZE * CHUNK_SIZE,
+                density: Density::Vegetation(1.0),
+                anchor: Anchor::Surface,
+                biomes: Vec::new(),
+                ground: grass.clone(),
+            },
+        );
+        registry.register(
+            "boulder",
+            Boulder {
+                block: BLOCKS.id("cobblestone"),
+                min_radius: 1,
+                max_radius: 2,
+            },
+            Placement {
+                attempts: 1,
+                density: Density::Fixed(0.05),
+                anchor: Anchor::Surface,
+                biomes: vec![Biome::Plains, Biome::Tundra],
+                ground: grass.clone(),
+            },
+        );
+        registry.register(
+            "flowers",
+            FlowerPatch {
+                flowers: vec![BLOCKS.id("dandelion"), BLOCKS.id("poppy")],
+                ground: grass.clone(),
+                spread: 3,
+                count: 8,
+            },
+            Placement {
+                attempts: 4,
+                density: Density::Vegetation(10.0),
+                anchor: Anchor::Surface,
+                biomes: vec![Biome::Plains, Biome::Forest],
+                ground: grass,
+            },
+        );
+        registry.register(
+            "gravel_blob",
+            Blob {
+                block: BLOCKS.id("gravel"),
+                replaces: vec![BLOCKS.id("stone")],
+                min_radius: 1,
+                max_radius: 3,
+            },
+            Placement {
+                attempts: 4,
+                density: Density::Fixed(1.0),
+                anchor: Anchor::Underground {
+                    min_y: 1,
+                    max_y: 48,
+                },
+                biomes: Vec::new(),
+                ground: Vec::new(),
+            },
+        );
+        registry.register(
+            "coal_blob",
+            Blob {
+                block: BLOCKS.id("coal_ore"),
+                replaces: vec![BLOCKS.id("stone")],
+                min_radius: 1,
+                max_radius: 2,
+            },
+            Placement {
+                attempts: 6,
+                density: Density::Fixed(1.0),
+                anchor: Anchor::Underground {
+                    min_y: 1,
+                    max_y: 64,
+                },
+                biomes: Vec::new(),
+                ground: Vec::new(),
+            },
+        );
+
+        registry
+    }
+
+    // 청크 기둥 하나를 꾸민다 - 이웃 기둥에서 넘어온 블록도 구조물이 있는 기둥 순서대로 놓는다
+    pub fn decorate(
+        &self,
+        generator: &WorldGenerator,
+        chunk_x: i32,
+        chunk_z: i32,
+        world: &mut dyn ColumnAccess,
+    ) {
+        let column = (chunk_x, chunk_z);
+        let reach = self.reach();
+
+        let mut blocks = Vec::new();
+        for origin_x in chunk_x - reach..=chunk_x + reach {
+            for origin_z in chunk_z - reach..=chunk_z + reach {
+                let placed = self.placed_features(generator, (origin_x, origin_z));
+                blocks.extend(
+                    placed
+                        .iter()
+                        .filter(|block| block.column() == column)
+                        .cloned(),
+                );
+            }
+        }
+
+        for feature_block in blocks {
+            let (x, y, z) = feature_block.position;
+            if let Some(existing) = world.get_block(x, y, z) {
+                if feature_block.replace.allows(world, (x, y, z), existing) {
+                    world.set_block(x, y, z, feature_block.block);
+                }
+            }
+        }
+    }
+
+    // 기둥 하나의 구조물 블록 - 캐시에 없으면 다시 놓아 본다 (시드와 기둥만으로 정해진다)
+    fn placed_features(
+        &self,
+        generator: &WorldGenerator,
+        column: (i32, i32),
+    ) -> Arc<Vec<FeatureBlock>> {
+        if let Some(blocks) = self.placed.lock().unwrap().get(column) {
+            return blocks;
+        }
+
+        let blocks = Arc::new(self.place_features(generator, column));
+        self.placed.lock().unwrap().insert(column, blocks.clone());
+        blocks
+    }
+
+    // 구조물이 닿을 수 있는 가장 먼 이웃 기둥까지의 거리 (청크)
+    fn reach(&self) -> i32 {
+        let size = CHUNK_SIZE as i32;
+        self.features
+            .iter()
+            .map(|feature| (feature.feature.radius() + size - 1) / size)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // 기둥 하나에 뿌리를 둔 구조물들이 쓰려는 블록 - 항상 같은 순서로 (구조물, 시도) 를 놓는다
+    fn place_features(
+        &self,
+        generator: &WorldGenerator,
+        (column_x, column_z): (i32, i32),
+    ) -> Vec<FeatureBlock> {
+        let mut writer = FeatureWriter { blocks: Vec::new() };
+
+        for (i, registered) in self.features.iter().enumerate() {
+            let mut rng = generator.column_rng(column_x, column_z, i as u64);
+
+            for _ in 0..registered.placement.attempts {
+                // 구조물의 모양은 따로 만든 난수로 정해서, 위치를 고르는 난수와 섞이지 않게 한다
+                let mut feature_rng = ChaCha8Rng::seed_from_u64(rng.gen());
+                let origin = FeatureRegistry::find_origin(
+                    generator,
+                    &registered.placement,
+                    column_x,
+                    column_z,
+                    &mut rng,
+                );
+
+                if let Some(origin) = origin {
+                    registered
+                        .feature
+                        .place(origin, &mut feature_rng, &mut writer);
+                }
+            }
+        }
+
+        writer.blocks
+    }
+
+    // 배치 규칙에 맞는 위치를 하나 고른다 (맞지 않으면 None)
+    fn find_origin(
+        generator: &WorldGenerator,
+        placement: &Placement,
+        column_x: i32,
+        column_z: i32,
+        rng: &mut ChaCha8Rng,
+    ) -> Option<(i32, i32, i32)> {
+        let size = CHUNK_SIZE as i32;
+        let x = column_x * size + rng.gen_range(0..size);
+        let z = column_z * size + rng.gen_range(0..size);
+        let roll = rng.gen::<f64>();
+        let height_roll = rng.gen::<f64>();
+
+        let biome = generator.biome_at(x, z);
+        if !placement.biomes.is_empty() && !placement.biomes.contains(&biome) {
+            return None;
+        }
+        if roll >= placement.density.chance(biome.settings()) {
+            return None;
+        }
+
+        match placement.anchor {
+            Anchor::Surface => {
+                let (y, ground) = generator.surface_at(x, z)?;
+                if !placement.ground.is_empty() && !placement.ground.contains(&ground) {
+                    return None;
+                }
+                Some((x, y, z))
+            }
+            Anchor::Underground { min_y, max_y } => {
+                let y = min_y + (height_roll * (max_y - min_y + 1) as f64) as i32;
+                Some((x, y, z))
+            }
+        }
+    }
+}
+
+pub struct Tree {
+    pub log: BlockID,
+    pub leaves: BlockID,
+    pub min_height: i32,
+    pub max_height: i32,
+}
+
+impl Feature for Tree {
+    fn radius(&self) -> i32 {
+        2
+    }
+
+    fn place(&self, (x, y, z): (i32, i32, i32), rng: &mut ChaCha8Rng, writer: &mut FeatureWriter) {
+        let h = rng.gen_range(self.min_height..=self.max_height);
+
+        // 줄기는 다른 나무의 잎을 뚫고 자랄 수 있다
+        for i in y + 1..y + 1 + h {
+            writer.replace_if(x, i, z, self.log, Replace::NonSolidOr(self.leaves));
+        }
+
+        for yy in y + h - 2..=y + h - 1 {
+            for xx in x - 2..=x + 2 {
+                for zz in z - 2..=z + 2 {
+                    if xx != x || zz != z {
+                        writer.place(xx, yy, zz, self.leaves);
+                    }
+                }
+            }
+        }
+
+        for xx in x - 1..=x + 1 {
+            for zz in z - 1..=z + 1 {
+                if xx != x || zz != z {
+                    writer.place(xx, y + h, zz, self.leaves);
+                }
+            }
+        }
+
+        writer.place(x, y + h + 1, z, self.leaves);
+        writer.place(x + 1, y + h + 1, z, self.leaves);
+        writer.place(x - 1, y + h + 1, z, self.leaves);
+        writer.place(x, y + h + 1, z + 1, self.leaves);
+        writer.place(x, y + h + 1, z - 1, self.leaves);
+    }
+}
+
+// 지표면에 반쯤 묻힌 둥근 바위
+pub struct Boulder {
+    pub block: BlockID,
+    pub min_radius: i32,
+    pub max_radius: i32,
+}
+
+impl Feature for Boulder {
+    fn radius(&self) -> i32 {
+        self.max_radius
+    }
+
+    fn place(&self, (x, y, z): (i32, i32, i32), rng: &mut ChaCha8Rng, writer: &mut FeatureWriter) {
+        let r = rng.gen_range(self.min_radius..=self.max_radius);
+
+        for dy in -r..=r {
+            for dz in -r..=r {
+                for dx in -r..=r {
+                    if dx * dx + dy * dy + dz * dz <= r * r {
+                        writer.place(x + dx, y + dy, z + dz, self.block);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// origin 주변의 풀 위에 꽃을 흩뿌린다
+pub struct FlowerPatch {
+    pub flowers: Vec<BlockID>,
+    pub ground: Vec<BlockID>,
+    pub spread: i32,
+    pub count: u32,
+}
+
+impl Feature for FlowerPatch {
+    fn radius(&self) -> i32 {
+        self.spread
+    }
+
+    fn place(&self, (x, y, z): (i32, i32, i32), rng: &mut ChaCha8Rng, writer: &mut FeatureWriter) {
+        let flower = self.flowers[rng.gen_range(0..self.flowers.len())];
+
+        for _ in 0..self.count {
+            let fx = x + rng.gen_range(-self.spread..=self.spread);
+            let fz = z + rng.gen_range(-self.spread..=self.spread);
+            let fy = y + rng.gen_range(-1..=1);
+
+            writer.replace_if(
+                fx,
+                fy + 1,
+                fz,
+                flower,
+                Replace::AirAbove(self.ground.clone()),
+            );
+        }
+    }
+}
+
+// 땅속의 둥근 덩어리 (자갈, 광석...)
+pub struct Blob {
+    pub block: BlockID,
+    pub replaces: Vec<BlockID>,
+    pub min_radius: i32,
+    pub max_radius: i32,
+}
+
+impl Feature for Blob {
+    fn radius(&self) -> i32 {
+        self.max_radius
+    }
+
+    fn place(&self, (x, y, z): (i32, i32, i32), rng: &mut ChaCha8Rng, writer: &mut FeatureWriter) {
+        let r = rng.gen_range(self.min_radius..=self.max_radius);
+        // 축마다 조금씩 늘이거나 줄여서 완전한 구가 되지 않게 한다
+        let stretch: [f64; 3] = [
+            rng.gen_range(0.7..1.3),
+            rng.gen_range(0.7..1.3),
+            rng.gen_range(0.7..1.3),
+        ];
+        let r2 = (r * r) as f64;
+
+        for dy in -r..=r {
+            for dz in -r..=r {
+                for dx in -r..=r {
+                    let d = (dx as f64 * stretch[0]).powi(2)
+                        + (dy as f64 * stretch[1]).powi(2)
+                        + (dz as f64 * stretch[2]).powi(2);
+                    if d <= r2 {
+                        let replace = Replace::AnyOf(self.replaces.clone());
+                        writer.replace_if(x + dx, y + dy, z + dz, self.block, replace);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placement_cache_drops_the_least_recently_used_column() {
+        let mut cache = PlacementCache::default();
+        for x in 0..FEATURE_CACHE_COLUMNS as i32 {
+            cache.insert((x, 0), Arc::new(Vec::new()));
+        }
+        assert!(cache.get((0, 0)).is_some());
+
+        cache.insert((-1, 0), Arc::new(Vec::new()));
+        assert_eq!(cache.columns.len(), FEATURE_CACHE_COLUMNS);
+        assert!(cache.get((0, 0)).is_some());
+        assert!(cache.get((1, 0)).is_none());
+        assert!(cache.get((-1, 0)).is_some());
+    }
+}
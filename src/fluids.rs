@@ -0,0 +1,235 @@
+use crate::block_registry::{FluidProperties, FluidState, BLOCKS};
+use crate::chunk::BlockID;
+use crate::chunk_manager::ChunkManager;
+
+const HORIZONTAL: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+// 유체가 옆으로 흐를 때 낭떠러지를 찾는 거리 - 가장 가까운 내리막 쪽으로만 흐른다
+const SLOPE_SEARCH_DISTANCE: u32 = 4;
+
+pub fn fluid_properties(block: BlockID) -> Option<FluidProperties> {
+    block.definition().fluid
+}
+
+// 유체 표면의 높이 (0..1) - 원천이 가장 높고 멀리 흐를수록 낮아진다
+pub fn fluid_height(block: BlockID) -> Option<f32> {
+    let state = block.fluid_state()?;
+    let fluid = fluid_properties(block)?;
+
+    if state.falling {
+        return Some(1.0);
+    }
+    Some(1.0 - (state.level as f32 + 1.0) / (fluid.flow_distance as f32 + 2.0))
+}
+
+fn same_fluid(block: BlockID, source: BlockID) -> Option<FluidState> {
+    block.fluid_state().filter(|state| state.source == source)
+}
+
+// 유체가 들어가서 덮어쓸 수 있는 블록 (공기, 꽃 같은 고체가 아닌 블록)
+fn is_replaceable(block: BlockID) -> bool {
+    block.is_air() || (!block.is_solid() && !block.is_fluid())
+}
+
+// 아래로 떨어지는 중인지 - 그렇다면 옆으로는 흐르지 않는다
+fn flows_down(world: &ChunkManager, x: i32, y: i32, z: i32, source: BlockID) -> bool {
+    match world.get_block(x, y - 1, z) {
+        Some(below) => match same_fluid(below, source) {
+            Some(state) => !state.is_source(),
+            None => is_replaceable(below),
+        },
+        // 아직 불러오지 않은 곳으로는 흐르지 않는다
+        None => false,
+    }
+}
+
+// 옆으로 퍼지는지 - 떨어지는 중이거나 고인 유체 위에 얹힌 흐름은 퍼지지 않고 합쳐진다
+fn spreads_sideways(world: &ChunkManager, x: i32, y: i32, z: i32, state: FluidState) -> bool {
+    match world.get_block(x, y - 1, z) {
+        Some(below) => match same_fluid(below, state.source) {
+            Some(below) => state.is_source() && below.is_source(),
+            None => !is_replaceable(below),
+        },
+        None => true,
+    }
+}
+
+// 흐름이 지나갈 수 있는 블록 - 내리막을 찾을 때는 이미 흐르고 있는 곳도 길로 친다
+fn is_passable(block: BlockID, source: BlockID) -> bool {
+    match same_fluid(block, source) {
+        Some(state) => !state.is_source(),
+        None => is_replaceable(block),
+    }
+}
+
+// 옆으로 흐를 수 있는지 - 같은 유체라도 더 멀리서 온 흐름은 덮어쓴다
+fn can_flow_into(block: BlockID, source: BlockID, level: u8) -> bool {
+    match same_fluid(block, source) {
+        Some(state) => !state.is_source() && !state.falling && state.level > level,
+        None => is_replaceable(block),
+    }
+}
+
+// 주변 유체로부터 흐르는 블록이 있어야 할 상태를 다시 계산한다 (None이면 말라서 사라진다)
+fn desired_state(
+    world: &ChunkManager,
+    x: i32,
+    y: i32,
+    z: i32,
+    source: BlockID,
+    fluid: FluidProperties,
+) -> Option<FluidState> {
+    if let Some(above) = world.get_block(x, y + 1, z) {
+        if same_fluid(above, source).is_some() {
+            return Some(FluidState {
+                source,
+                level: 0,
+                falling: true,
+            });
+        }
+    }
+
+    let mut level = None;
+    for (dx, dz) in HORIZONTAL {
+        let (nx, nz) = (x + dx, z + dz);
+        let neighbor = match world
+            .get_block(nx, y, nz)
+            .and_then(|b| same_fluid(b, source))
+        {
+            Some(state) => state,
+            None => continue,
+        };
+        if !spreads_sideways(world, nx, y, nz, neighbor) {
+            continue;
+        }
+
+        let fed = if neighbor.falling {
+            1
+        } else {
+            neighbor.level + 1
+        };
+        level = Some(level.map_or(fed, |level: u8| level.min(fed)));
+    }
+
+    level
+        .filter(|&level| level <= fluid.flow_distance)
+        .map(|level| FluidState {
+            source,
+            level,
+            falling: false,
+        })
+}
+
+// 한 칸 옆으로 간 위치에서 낭떠러지까지의 거리
+fn slope_distance(
+    world: &ChunkManager,
+    (x, y, z): (i32, i32, i32),
+    from: (i32, i32),
+    source: BlockID,
+    depth: u32,
+) -> Option<u32> {
+    if flows_down(world, x, y, z, source) {
+        return Some(depth);
+    }
+    if depth >= SLOPE_SEARCH_DISTANCE {
+        return None;
+    }
+
+    HORIZONTAL
+        .into_iter()
+        .filter(|&direction| direction != (-from.0, -from.1))
+        .filter_map(|(dx, dz)| {
+            let next = (x + dx, y, z + dz);
+            world
+                .get_block(next.0, next.1, next.2)
+                .filter(|&block| is_passable(block, source))
+                .and_then(|_| slope_distance(world, next, (dx, dz), source, depth + 1))
+        })
+        .min()
+}
+
+fn spread(
+    world: &mut ChunkManager,
+    x: i32,
+    y: i32,
+    z: i32,
+    state: FluidState,
+    fluid: FluidProperties,
+) {
+    let source = state.source;
+
+    if flows_down(world, x, y, z, source) {
+        let falling = FluidState {
+            source,
+            level: 0,
+            falling: true,
+        };
+        let below = world.get_block(x, y - 1, z).unwrap();
+        if below.fluid_state() != Some(falling) {
+            world.set_block(x, y - 1, z, BLOCKS.fluid_block(falling).unwrap());
+        }
+        return;
+    }
+    if !spreads_sideways(world, x, y, z, state) {
+        return;
+    }
+
+    let level = if state.falling { 1 } else { state.level + 1 };
+    if level > fluid.flow_distance {
+        return;
+    }
+
+    let targets: Vec<((i32, i32), Option<u32>)> = HORIZONTAL
+        .into_iter()
+        .filter(|&(dx, dz)| {
+            world
+                .get_block(x + dx, y, z + dz)
+                .map_or(false, |block| is_passable(block, source))
+        })
+        .map(|(dx, dz)| {
+            let distance = slope_distance(world, (x + dx, y, z + dz), (dx, dz), source, 0);
+            ((dx, dz), distance)
+        })
+        .collect();
+
+    // 가까운 내리막이 있으면 그쪽으로만, 없으면 사방으로 흐른다
+    let nearest = targets.iter().filter_map(|&(_, distance)| distance).min();
+    let flowing = BLOCKS
+        .fluid_block(FluidState {
+            source,
+            level,
+            falling: false,
+        })
+        .unwrap();
+
+    for ((dx, dz), distance) in targets {
+        if nearest.is_some() && distance != nearest {
+            continue;
+        }
+        if can_flow_into(world.get_block(x + dx, y, z + dz).unwrap(), source, level) {
+            world.set_block(x + dx, y, z + dz, flowing);
+        }
+    }
+}
+
+// 예약된 유체 업데이트 하나를 처리한다
+pub fn update_fluid(world: &mut ChunkManager, x: i32, y: i32, z: i32) {
+    let block = match world.get_block(x, y, z) {
+        Some(block) => block,
+        None => return,
+    };
+    let (state, fluid) = match (block.fluid_state(), fluid_properties(block)) {
+        (Some(state), Some(fluid)) => (state, fluid),
+        _ => return,
+    };
+
+    if !state.is_source() {
+        let desired = desired_state(world, x, y, z, state.source, fluid);
+        if desired != Some(state) {
+            let block = desired.map_or(BlockID::AIR, |state| BLOCKS.fluid_block(state).unwrap());
+            world.set_block(x, y, z, block);
+            return;
+        }
+    }
+
+    spread(world, x, y, z, state, fluid);
+}
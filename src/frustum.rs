@@ -0,0 +1,110 @@
+use crate::aabb::AABB;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+
+// 카메라에 보이는 공간 - 평면 6개 (left, right, bottom, top, near, far), normal은 안쪽을 향한다
+#[derive(Copy, Clone, Debug)]
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    // projection * view 행렬의 행에서 평면을 꺼낸다 (Gribb & Hartmann)
+    pub fn from_matrix(projection_view: &Mat4) -> Self {
+        let row = |i: usize| -> Vec4 { projection_view.row(i).transpose() };
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+
+        let planes = [w + x, w - x, w + y, w - y, w + z, w - z].map(|plane| {
+            let length = plane.xyz().norm();
+            plane / length
+        });
+
+        Self { planes }
+    }
+
+    // 상자가 평면 하나라도 완전히 바깥에 있으면 보이지 않는다
+    pub fn intersects_aabb(&self, aabb: &AABB) -> bool {
+        self.planes.iter().all(|plane| {
+            let normal = plane.xyz();
+            let corner = Vec3::new(
+                if normal.x >= 0.0 {
+                    aabb.maxs.x
+                } else {
+                    aabb.mins.x
+                },
+                if normal.y >= 0.0 {
+                    aabb.maxs.y
+                } else {
+                    aabb.mins.y
+                },
+                if normal.z >= 0.0 {
+                    aabb.maxs.z
+                } else {
+                    aabb.mins.z
+                },
+            );
+            normal.dot(&corner) + plane.w >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::{look_at, perspective, vec3};
+    use std::f32::consts::FRAC_PI_2;
+
+    // 원점에서 -z를 보는 카메라 - 시야각이 90도라서 옆 평면은 |x| = -z, |y| = -z
+    fn frustum() -> Frustum {
+        let projection = perspective(1.0, FRAC_PI_2, 0.1, 100.0);
+        let view = look_at(
+            &vec3(0.0, 0.0, 0.0),
+            &vec3(0.0, 0.0, -1.0),
+            &vec3(0.0, 1.0, 0.0),
+        );
+        Frustum::from_matrix(&(projection * view))
+    }
+
+    fn unit_box(x: f32, y: f32, z: f32) -> AABB {
+        AABB::new(vec3(x, y, z), vec3(x + 1.0, y + 1.0, z + 1.0))
+    }
+
+    #[test]
+    fn accepts_a_box_in_front() {
+        assert!(frustum().intersects_aabb(&unit_box(-0.5, -0.5, -10.0)));
+    }
+
+    #[test]
+    fn rejects_a_box_behind() {
+        assert!(!frustum().intersects_aabb(&unit_box(-0.5, -0.5, 5.0)));
+    }
+
+    #[test]
+    fn rejects_boxes_outside_each_plane() {
+        let frustum = frustum();
+        // left, right, bottom, top, far (near는 뒤쪽 상자로 확인)
+        for aabb in [
+            unit_box(-20.0, -0.5, -10.0),
+            unit_box(19.0, -0.5, -10.0),
+            unit_box(-0.5, -20.0, -10.0),
+            unit_box(-0.5, 19.0, -10.0),
+            unit_box(-0.5, -0.5, -200.0),
+        ] {
+            assert!(!frustum.intersects_aabb(&aabb), "{:?}", aabb.mins);
+        }
+    }
+
+    #[test]
+    fn accepts_boxes_straddling_a_plane() {
+        let frustum = frustum();
+        for aabb in [
+            // 오른쪽 평면 (x = 10)에 걸친 상자
+            unit_box(9.5, -0.5, -10.0),
+            // 먼 평면에 걸친 상자
+            unit_box(-0.5, -0.5, -100.5),
+            // 카메라를 감싸는 상자 (가까운 평면에 걸친다)
+            AABB::new(vec3(-1.0, -1.0, -1.0), vec3(1.0, 1.0, 1.0)),
+        ] {
+            assert!(frustum.intersects_aabb(&aabb), "{:?}", aabb.mins);
+        }
+    }
+}
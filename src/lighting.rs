@@ -0,0 +1,561 @@
+use crate::chunk::{BlockID, Chunk};
+use crate::chunk_manager::{ChunkManager, CHUNK_SIZE, CHUNK_VOLUME};
+use crate::constants::WORLD_HEIGHT_CHUNKS;
+use crate::region::ChunkCoords;
+use std::collections::{HashMap, VecDeque};
+
+pub const MAX_LIGHT: u8 = 15;
+
+const WORLD_HEIGHT: i32 = WORLD_HEIGHT_CHUNKS * CHUNK_SIZE as i32;
+
+pub const NEIGHBOURS: [(i32, i32, i32); 6] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LightChannel {
+    Sky,
+    Block,
+}
+
+// 블록마다 빛 1바이트 (상위 4비트: 하늘빛, 하위 4비트: 블록 빛)
+#[derive(Clone, Debug)]
+pub enum LightStorage {
+    Uniform(u8),
+    Full(Vec<u8>),
+}
+
+impl LightStorage {
+    #[inline]
+    pub fn get(&self, i: usize) -> u8 {
+        match self {
+            LightStorage::Uniform(light) => *light,
+            LightStorage::Full(lights) => lights[i],
+        }
+    }
+
+    pub fn set(&mut self, i: usize, light: u8) {
+        match self {
+            LightStorage::Uniform(current) => {
+                if *current == light {
+                    return;
+                }
+
+                let mut lights = vec![*current; CHUNK_VOLUME as usize];
+                lights[i] = light;
+                *self = LightStorage::Full(lights);
+            }
+            LightStorage::Full(lights) => lights[i] = light,
+        }
+    }
+
+    pub fn optimize(&mut self) {
+        if let LightStorage::Full(lights) = self {
+            if lights.iter().all(|&light| light == lights[0]) {
+                *self = LightStorage::Uniform(lights[0]);
+            }
+        }
+    }
+}
+
+#[inline]
+pub fn sky_light(packed: u8) -> u8 {
+    packed >> 4
+}
+
+#[inline]
+pub fn block_light(packed: u8) -> u8 {
+    packed & 0x0F
+}
+
+#[inline]
+pub fn pack_light(sky: u8, block: u8) -> u8 {
+    (sky << 4) | block
+}
+
+fn get_block(chunks: &HashMap<ChunkCoords, Chunk>, x: i32, y: i32, z: i32) -> Option<BlockID> {
+    let (cx, cy, cz, bx, by, bz) = ChunkManager::get_chunk_and_block_coords(x, y, z);
+    chunks
+        .get(&(cx, cy, cz))
+        .map(|chunk| chunk.get_block(bx, by, bz))
+}
+
+// 불러오지 않은 곳은 None, 월드 위쪽은 항상 하늘빛 최대
+fn get_light(
+    chunks: &HashMap<ChunkCoords, Chunk>,
+    x: i32,
+    y: i32,
+    z: i32,
+    channel: LightChannel,
+) -> Option<u8> {
+    if y >= WORLD_HEIGHT {
+        return Some(if channel == LightChannel::Sky {
+            MAX_LIGHT
+        } else {
+            0
+        });
+    }
+
+    let (cx, cy, cz, bx, by, bz) = ChunkManager::get_chunk_and_block_coords(x, y, z);
+    chunks.get(&(cx, cy, cz)).map(|chunk| match channel {
+        LightChannel::Sky => chunk.sky_light(bx, by, bz),
+        LightChannel::Block => chunk.block_light(bx, by, bz),
+    })
+}
+
+fn set_light(
+    chunks: &mut HashMap<ChunkCoords, Chunk>,
+    x: i32,
+    y: i32,
+    z: i32,
+    channel: LightChannel,
+    level: u8,
+) {
+    let (cx, cy, cz, bx, by, bz) = ChunkManager::get_chunk_and_block_coords(x, y, z);
+    if let Some(chunk) = chunks.get_mut(&(cx, cy, cz)) {
+        match channel {
+            LightChannel::Sky => chunk.set_sky_light(bx, by, bz, level),
+            LightChannel::Block => chunk.set_block_light(bx, by, bz, level),
+        }
+    }
+}
+
+// 큐에 든 블록에서부터 budget만큼 빛을 퍼뜨린다 (한 칸마다 1씩 약해짐)
+fn propagate_light(
+    chunks: &mut HashMap<ChunkCoords, Chunk>,
+    channel: LightChannel,
+    queue: &mut VecDeque<(i32, i32, i32)>,
+    budget: &mut usize,
+) {
+    while *budget > 0 {
+        let (x, y, z) = match queue.pop_front() {
+            Some(position) => position,
+            None => break,
+        };
+        *budget -= 1;
+
+        let level = match get_light(chunks, x, y, z, channel) {
+            Some(level) if level > 0 => level,
+            _ => continue,
+        };
+
+        for (dx, dy, dz) in NEIGHBOURS {
+            let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+            let neighbour = match get_block(chunks, nx, ny, nz) {
+                Some(block) if block.is_transparent() => block,
+                _ => continue,
+            };
+
+            let new_level = if channel == LightChannel::Sky
+                && dy == -1
+                && level == MAX_LIGHT
+                && neighbour.is_air()
+            {
+                MAX_LIGHT
+            } else {
+                level - 1
+            };
+
+            if new_level > get_light(chunks, nx, ny, nz, channel).unwrap_or(MAX_LIGHT) {
+                set_light(chunks, nx, ny, nz, channel, new_level);
+                queue.push_back((nx, ny, nz));
+            }
+        }
+    }
+}
+
+// 큐가 빌 때까지 한 번에 퍼뜨린다
+fn propagate_all(
+    chunks: &mut HashMap<ChunkCoords, Chunk>,
+    channel: LightChannel,
+    mut queue: VecDeque<(i32, i32, i32)>,
+) {
+    let mut budget = usize::MAX;
+    propagate_light(chunks, channel, &mut queue, &mut budget);
+}
+
+// 빛이 사라진 블록에서부터, 그 빛에 의존하던 블록들을 어둡게 만든다
+fn remove_light(
+    chunks: &mut HashMap<ChunkCoords, Chunk>,
+    channel: LightChannel,
+    queue: &mut VecDeque<((i32, i32, i32), u8)>,
+    relight: &mut VecDeque<(i32, i32, i32)>,
+    budget: &mut usize,
+) {
+    while *budget > 0 {
+        let ((x, y, z), level) = match queue.pop_front() {
+            Some(entry) => entry,
+            None => break,
+        };
+        *budget -= 1;
+
+        for (dx, dy, dz) in NEIGHBOURS {
+            let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+            if ny >= WORLD_HEIGHT {
+                continue;
+            }
+
+            let neighbour_level = match get_light(chunks, nx, ny, nz, channel) {
+                Some(level) if level > 0 => level,
+                _ => continue,
+            };
+
+            let dependent = neighbour_level < level
+                || (channel == LightChannel::Sky && dy == -1 && level == MAX_LIGHT);
+
+            if dependent {
+                set_light(chunks, nx, ny, nz, channel, 0);
+                queue.push_back(((nx, ny, nz), neighbour_level));
+
+                // 광원은 스스로 다시 빛난다
+                if channel == LightChannel::Block {
+                    let emission = get_block(chunks, nx, ny, nz)
+                        .map_or(0, |block| block.definition().light_emission);
+                    if emission > 0 {
+                        set_light(chunks, nx, ny, nz, channel, emission);
+                        relight.push_back((nx, ny, nz));
+                    }
+                }
+            } else {
+                relight.push_back((nx, ny, nz));
+            }
+        }
+    }
+}
+
+// (x, y, z)의 블록이 바뀐 뒤에 호출한다
+pub fn update_light(chunks: &mut HashMap<ChunkCoords, Chunk>, x: i32, y: i32, z: i32) {
+    update_lights(chunks, &[(x, y, z)]);
+}
+
+// 여러 블록이 한꺼번에 바뀐 뒤에 호출한다 (폭발 등)
+pub fn update_lights(chunks: &mut HashMap<ChunkCoords, Chunk>, positions: &[(i32, i32, i32)]) {
+    let mut updates = LightUpdates::default();
+    updates.queue(chunks, positions);
+    updates.run(chunks, usize::MAX);
+}
+
+// 채널 하나의 밀린 빛 계산 - 지우기가 끝나야 바뀐 블록에서 다시 빛을 채운다
+#[derive(Default)]
+struct ChannelUpdates {
+    removal: VecDeque<((i32, i32, i32), u8)>,
+    seeds: Vec<(i32, i32, i32)>,
+    relight: VecDeque<(i32, i32, i32)>,
+}
+
+impl ChannelUpdates {
+    fn is_empty(&self) -> bool {
+        self.removal.is_empty() && self.seeds.is_empty() && self.relight.is_empty()
+    }
+
+    fn run(
+        &mut self,
+        chunks: &mut HashMap<ChunkCoords, Chunk>,
+        channel: LightChannel,
+        budget: &mut usize,
+    ) {
+        remove_light(
+            chunks,
+            channel,
+            &mut self.removal,
+            &mut self.relight,
+            budget,
+        );
+        if !self.removal.is_empty() {
+            return;
+        }
+
+        for (x, y, z) in self.seeds.drain(..) {
+            let block = match get_block(chunks, x, y, z) {
+                Some(block) => block,
+                None => continue,
+            };
+
+            if channel == LightChannel::Block && block.definition().light_emission > 0 {
+                set_light(chunks, x, y, z, channel, block.definition().light_emission);
+                self.relight.push_back((x, y, z));
+            }
+
+            if block.is_transparent() {
+                for (dx, dy, dz) in NEIGHBOURS {
+                    self.relight.push_back((x + dx, y + dy, z + dz));
+                }
+            }
+        }
+
+        propagate_light(chunks, channel, &mut self.relight, budget);
+    }
+}
+
+// 블록이 바뀐 뒤의 빛 계산을 여러 프레임에 나눠 한다
+#[derive(Default)]
+pub struct LightUpdates {
+    sky: ChannelUpdates,
+    block: ChannelUpdates,
+}
+
+impl LightUpdates {
+    pub fn is_empty(&self) -> bool {
+        self.sky.is_empty() && self.block.is_empty()
+    }
+
+    pub fn queue(
+        &mut self,
+        chunks: &mut HashMap<ChunkCoords, Chunk>,
+        positions: &[(i32, i32, i32)],
+    ) {
+        for (channel, updates) in [
+            (LightChannel::Sky, &mut self.sky),
+            (LightChannel::Block, &mut self.block),
+        ] {
+            for &(x, y, z) in positions {
+                let old_level = get_light(chunks, x, y, z, channel).unwrap_or(0);
+                if old_level > 0 {
+                    set_light(chunks, x, y, z, channel, 0);
+                    updates.removal.push_back(((x, y, z), old_level));
+                }
+                updates.seeds.push((x, y, z));
+            }
+        }
+    }
+
+    pub fn run(&mut self, chunks: &mut HashMap<ChunkCoords, Chunk>, max_steps: usize) {
+        let mut budget = max_steps;
+        self.sky.run(chunks, LightChannel::Sky, &mut budget);
+        self.block.run(chunks, LightChannel::Block, &mut budget);
+    }
+}
+
+// 새로 생성된 청크 기둥 하나의 빛을 계산한다 (워커 스레드 - 옆 기둥과는 불러온 뒤에 잇는다)
+pub fn light_column(chunks: &mut HashMap<ChunkCoords, Chunk>, chunk_x: i32, chunk_z: i32) {
+    let size = CHUNK_SIZE as i32;
+    let (base_x, base_z) = (chunk_x * size, chunk_z * size);
+
+    // 위에서부터 공기를 따라 내려가며 하늘빛을 채운다 (heights[z][x]: 하늘이 보이는 가장 낮은 y)
+    let mut heights = [[WORLD_HEIGHT; CHUNK_SIZE as usize]; CHUNK_SIZE as usize];
+    for chunk_y in (0..WORLD_HEIGHT_CHUNKS).rev() {
+        let chunk = match chunks.get_mut(&(chunk_x, chunk_y, chunk_z)) {
+            Some(chunk) => chunk,
+            None => continue,
+        };
+
+        let all_open = heights.iter().flatten().all(|&h| h == (chunk_y + 1) * size);
+        if all_open && chunk.blocks().is_uniform() == Some(BlockID::AIR) {
+            chunk.fill_light(pack_light(MAX_LIGHT, 0));
+            heights = [[chunk_y * size; CHUNK_SIZE as usize]; CHUNK_SIZE as usize];
+            continue;
+        }
+
+        for y in (0..size).rev() {
+            for z in 0..size {
+                for x in 0..size {
+                    let height = &mut heights[z as usize][x as usize];
+                    if *height != chunk_y * size + y + 1 {
+                        continue;
+                    }
+
+                    if chunk.get_block(x as u32, y as u32, z as u32).is_air() {
+                        chunk.set_sky_light(x as u32, y as u32, z as u32, MAX_LIGHT);
+                        *height -= 1;
+                    }
+                }
+            }
+        }
+    }
+
+    // 옆 기둥보다 높이 드러난 부분에서만 옆으로 퍼뜨리면 된다
+    let mut queue = VecDeque::new();
+    for z in 0..size {
+        for x in 0..size {
+            let height = heights[z as usize][x as usize];
+            let mut highest = height;
+            for (dx, _, dz) in NEIGHBOURS {
+                let (nx, nz) = (x + dx, z + dz);
+                if (0..size).contains(&nx) && (0..size).contains(&nz) {
+                    highest = highest.max(heights[nz as usize][nx as usize]);
+                }
+            }
+
+            for y in height..=highest.min(WORLD_HEIGHT - 1) {
+                queue.push_back((base_x + x, y, base_z + z));
+            }
+        }
+    }
+    propagate_all(chunks, LightChannel::Sky, queue);
+
+    let mut queue = VecDeque::new();
+    for chunk_y in 0..WORLD_HEIGHT_CHUNKS {
+        let chunk = match chunks.get_mut(&(chunk_x, chunk_y, chunk_z)) {
+            Some(chunk) => chunk,
+            None => continue,
+        };
+        if let Some(block) = chunk.blocks().is_uniform() {
+            if block.definition().light_emission == 0 {
+                continue;
+            }
+        }
+
+        for y in 0..size {
+            for z in 0..size {
+                for x in 0..size {
+                    let (x, y, z) = (x as u32, y as u32, z as u32);
+                    let emission = chunk.get_block(x, y, z).definition().light_emission;
+                    if emission > 0 {
+                        chunk.set_block_light(x, y, z, emission);
+                        queue.push_back((
+                            base_x + x as i32,
+                            chunk_y * size + y as i32,
+                            base_z + z as i32,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    propagate_all(chunks, LightChannel::Block, queue);
+
+    for chunk in chunks.values_mut() {
+        chunk.optimize_light();
+    }
+}
+
+// 이미 불러온 옆 기둥과 맞닿은 면을 통해 빛을 주고받는다 (메인 스레드)
+pub fn propagate_across_column_borders(
+    chunks: &mut HashMap<ChunkCoords, Chunk>,
+    chunk_x: i32,
+    chunk_z: i32,
+) {
+    let size = CHUNK_SIZE as i32;
+
+    for channel in [LightChannel::Sky, LightChannel::Block] {
+        let mut queue = VecDeque::new();
+
+        for (dx, _, dz) in NEIGHBOURS {
+            if (dx, dz) == (0, 0) || !chunks.contains_key(&(chunk_x + dx, 0, chunk_z + dz)) {
+                continue;
+            }
+
+            for i in 0..size {
+                // 경계를 사이에 둔 두 블록
+                let (x, z) = match (dx, dz) {
+                    (1, _) => (size - 1, i),
+                    (-1, _) => (0, i),
+                    (_, 1) => (i, size - 1),
+                    _ => (i, 0),
+                };
+                let inside = (chunk_x * size + x, chunk_z * size + z);
+                let outside = (inside.0 + dx, inside.1 + dz);
+
+                for y in 0..WORLD_HEIGHT {
+                    let a = get_light(chunks, inside.0, y, inside.1, channel).unwrap_or(0);
+                    let b = get_light(chunks, outside.0, y, outside.1, channel).unwrap_or(0);
+
+                    // 차이가 1 이하라면 빛이 넘어갈 일이 없다
+                    if a > b + 1 {
+                        queue.push_back((inside.0, y, inside.1));
+                    } else if b > a + 1 {
+                        queue.push_back((outside.0, y, outside.1));
+                    }
+                }
+            }
+        }
+
+        propagate_all(chunks, channel, queue);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_registry::BLOCKS;
+
+    fn stone_column() -> HashMap<ChunkCoords, Chunk> {
+        let mut chunks = HashMap::new();
+        chunks.insert((0, 0, 0), Chunk::full_of_block(BLOCKS.id("stone")));
+        for chunk_y in 1..WORLD_HEIGHT_CHUNKS {
+            chunks.insert((0, chunk_y, 0), Chunk::empty());
+        }
+        light_column(&mut chunks, 0, 0);
+        chunks
+    }
+
+    fn set_blocks(
+        chunks: &mut HashMap<ChunkCoords, Chunk>,
+        edits: &[((i32, i32, i32), BlockID)],
+    ) -> Vec<(i32, i32, i32)> {
+        let size = CHUNK_SIZE as i32;
+        for &((x, y, z), block) in edits {
+            let chunk = chunks.get_mut(&(x / size, y / size, z / size)).unwrap();
+            chunk.set_block(
+                (x % size) as u32,
+                (y % size) as u32,
+                (z % size) as u32,
+                block,
+            );
+        }
+        edits.iter().map(|&(position, _)| position).collect()
+    }
+
+    #[test]
+    fn capped_updates_match_a_full_update() {
+        let stone = BLOCKS.id("stone");
+        let lamp = BLOCKS.id("debug2");
+        let size = CHUNK_SIZE as i32;
+
+        // 땅을 파서 구멍 바닥에 광원을 두고, 그 위를 지붕으로 덮는다
+        let mut edits = Vec::new();
+        for x in 2..8 {
+            for z in 2..8 {
+                for y in size - 6..size {
+                    edits.push(((x, y, z), BlockID::AIR));
+                }
+                edits.push(((x, size + 4, z), stone));
+            }
+        }
+        edits.push(((4, size - 6, 4), lamp));
+
+        let mut expected = stone_column();
+        let positions = set_blocks(&mut expected, &edits);
+        update_lights(&mut expected, &positions);
+
+        let mut chunks = stone_column();
+        let positions = set_blocks(&mut chunks, &edits);
+        let mut updates = LightUpdates::default();
+        updates.queue(&mut chunks, &positions);
+        let mut runs = 0;
+        while !updates.is_empty() {
+            updates.run(&mut chunks, 50);
+            runs += 1;
+        }
+        assert!(runs > 1, "The update should not fit in a single run");
+        assert_eq!(
+            get_light(&chunks, 4, size - 5, 4, LightChannel::Block),
+            Some(14)
+        );
+        assert_eq!(
+            get_light(&chunks, 4, size + 3, 4, LightChannel::Sky),
+            Some(MAX_LIGHT - 3)
+        );
+
+        for (coords, chunk) in &expected {
+            for y in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    for x in 0..CHUNK_SIZE {
+                        assert_eq!(
+                            chunks[coords].get_light(x, y, z),
+                            chunk.get_light(x, y, z),
+                            "Light differs at {:?} in chunk {:?}",
+                            (x, y, z),
+                            coords
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
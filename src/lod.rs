@@ -0,0 +1,548 @@
+use crate::chunk::BlockID;
+use crate::chunk_manager::CHUNK_SIZE;
+use crate::chunk_mesh::{LayeredChunkMesh, MeshArena};
+use crate::constants::{
+    LOD_DISTANCES, LOD_SCALES, LOD_SKIRT_DEPTH, MAX_LOD_TILES_MESHED_PER_FRAME,
+};
+use crate::mesher::{build_box_face, MeshLayers};
+use crate::worker_pool::WorkerPool;
+use crate::world_gen::WorldGenerator;
+use crate::UVMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+
+// 타일 한 변의 칸 수 - 칸 하나가 scale x scale 블록이므로 타일은 청크 기둥 scale x scale개를 덮는다
+const TILE_CELLS: i32 = CHUNK_SIZE as i32;
+// 메시 조각 하나의 높이 (칸) - packed vertex의 위치는 32칸보다 작아야 한다
+const SLICE_CELLS: i32 = CHUNK_SIZE as i32;
+
+// 멀리 있는 지형을 합쳐서 그리는 타일 - x, z는 타일이 시작하는 청크 기둥 좌표 (scale의 배수)
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct LodTile {
+    pub scale: i32,
+    pub x: i32,
+    pub z: i32,
+}
+
+impl LodTile {
+    fn containing(scale: i32, (x, z): (i32, i32)) -> LodTile {
+        LodTile {
+            scale,
+            x: x.div_euclid(scale) * scale,
+            z: z.div_euclid(scale) * scale,
+        }
+    }
+
+    fn level(&self) -> usize {
+        LOD_SCALES
+            .iter()
+            .position(|&scale| scale == self.scale)
+            .unwrap()
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        (self.z..self.z + self.scale)
+            .flat_map(move |z| (self.x..self.x + self.scale).map(move |x| (x, z)))
+    }
+
+    // 청크 기둥 (x, z)에서 타일의 가장 가까운 기둥까지 거리의 제곱 (청크)
+    fn distance_squared(&self, (x, z): (i32, i32)) -> i32 {
+        let dx = (self.x - x).max(x - (self.x + self.scale - 1)).max(0);
+        let dz = (self.z - z).max(z - (self.z + self.scale - 1)).max(0);
+        dx * dx + dz * dz
+    }
+
+    fn children(&self) -> [LodTile; 4] {
+        let half = self.scale / 2;
+        [(0, 0), (half, 0), (0, half), (half, half)].map(|(dx, dz)| LodTile {
+            scale: half,
+            x: self.x + dx,
+            z: self.z + dz,
+        })
+    }
+
+    // 타일이 시작하는 블록 좌표 (x, z)
+    pub fn origin(&self) -> (i32, i32) {
+        (self.x * CHUNK_SIZE as i32, self.z * CHUNK_SIZE as i32)
+    }
+
+    pub fn size(&self) -> i32 {
+        self.scale * CHUNK_SIZE as i32
+    }
+}
+
+// 가장 큰 타일부터 가까운 것을 네 개로 나눠 가며 거리에 맞는 단계의 타일을 고른다
+pub fn select_tiles(center: (i32, i32), is_loaded: impl Fn((i32, i32)) -> bool) -> Vec<LodTile> {
+    let coarsest = *LOD_SCALES.last().unwrap();
+    let range = *LOD_DISTANCES.last().unwrap();
+    let first = LodTile::containing(coarsest, (center.0 - range, center.1 - range));
+    let last = LodTile::containing(coarsest, (center.0 + range, center.1 + range));
+
+    let mut tiles = Vec::new();
+    let mut stack = Vec::new();
+    for z in (first.z..=last.z).step_by(coarsest as usize) {
+        for x in (first.x..=last.x).step_by(coarsest as usize) {
+            let tile = LodTile {
+                scale: coarsest,
+                x,
+                z,
+            };
+            if tile.distance_squared(center) <= range * range {
+                stack.push(tile);
+            }
+        }
+    }
+
+    while let Some(tile) = stack.pop() {
+        let level = tile.level();
+        let split = if level == 0 {
+            tile.columns().all(&is_loaded)
+        } else {
+            tile.distance_squared(center) < LOD_DISTANCES[level - 1] * LOD_DISTANCES[level - 1]
+        };
+
+        if !split {
+            tiles.push(tile);
+        } else if level > 0 {
+            stack.extend(tile.children());
+        }
+    }
+
+    tiles
+}
+
+// 타일의 칸마다 지표면의 높이와 블록 - 이웃 타일 쪽으로 한 칸씩 더 읽는다
+fn sample_surface(generator: &WorldGenerator, tile: &LodTile) -> Vec<(i32, BlockID)> {
+    let (origin_x, origin_z) = tile.origin();
+    let mut surface = Vec::with_capacity(((TILE_CELLS + 2) * (TILE_CELLS + 2)) as usize);
+
+    for j in -1..=TILE_CELLS {
+        for i in -1..=TILE_CELLS {
+            let x = origin_x + i * tile.scale + tile.scale / 2;
+            let z = origin_z + j * tile.scale + tile.scale / 2;
+            surface.push(generator.lod_surface_at(x, z));
+        }
+    }
+
+    surface
+}
+
+// 칸마다 윗면과, 이웃 칸보다 높은 만큼의 옆면을 그린다 - SLICE_CELLS 높이의 조각으로 나눈다
+pub fn build_lod_mesh(
+    generator: &WorldGenerator,
+    tile: &LodTile,
+    uv_map: &UVMap,
+) -> Vec<(i32, MeshLayers)> {
+    mesh_surface(&sample_surface(generator, tile), tile, uv_map)
+}
+
+// sample_surface가 읽은 지표면으로 메시 조각들을 만든다
+fn mesh_surface(
+    surface: &[(i32, BlockID)],
+    tile: &LodTile,
+    uv_map: &UVMap,
+) -> Vec<(i32, MeshLayers)> {
+    let get = |i: i32, j: i32| surface[((j + 1) * (TILE_CELLS + 2) + (i + 1)) as usize];
+    let scale = tile.scale as f32;
+
+    // 가장 낮은 skirt의 아래 끝부터 가장 높은 윗면까지를 조각으로 나눈다
+    let lowest = surface.iter().map(|&(height, _)| height).min().unwrap();
+    let highest = surface.iter().map(|&(height, _)| height).max().unwrap();
+    let base_y = lowest - LOD_SKIRT_DEPTH * tile.scale;
+    let slice_height = SLICE_CELLS * tile.scale;
+    let slice_count = (highest + 1 - base_y + slice_height - 1) / slice_height;
+    let slice_base = |slice: i32| base_y + slice * slice_height;
+    let cells = |y: i32, slice: i32| (y - slice_base(slice)) as f32 / scale;
+
+    let mut slices = vec![MeshLayers::default(); slice_count as usize];
+    for j in 0..TILE_CELLS {
+        for i in 0..TILE_CELLS {
+            let (height, block) = get(i, j);
+            let top = height + 1;
+            let (x, z) = (i as f32, j as f32);
+
+            // 조각의 윗끝에 걸친 윗면은 아래 조각에 넣는다
+            let slice = (top - 1 - base_y) / slice_height;
+            build_box_face(
+                &mut slices[slice as usize],
+                block,
+                uv_map,
+                2,
+                [x, 0.0, z],
+                [1.0, cells(top, slice), 1.0],
+                scale,
+            );
+
+            // right, left, front, back (Sides 순서의 면 번호)
+            for (side, (di, dj)) in [(0, (1, 0)), (1, (-1, 0)), (4, (0, 1)), (5, (0, -1))] {
+                let (ni, nj) = (i + di, j + dj);
+                let on_border = ni < 0 || ni >= TILE_CELLS || nj < 0 || nj >= TILE_CELLS;
+
+                let mut bottom = get(ni, nj).0 + 1;
+                if on_border {
+                    bottom = bottom.min(top) - LOD_SKIRT_DEPTH * tile.scale;
+                }
+                if bottom >= top {
+                    continue;
+                }
+
+                // 조각 경계는 블록 경계이므로 나눠도 텍스쳐가 이어진다
+                let first = (bottom - base_y) / slice_height;
+                let last = (top - 1 - base_y) / slice_height;
+                for slice in first..=last {
+                    let (lower, upper) = (
+                        bottom.max(slice_base(slice)),
+                        top.min(slice_base(slice + 1)),
+                    );
+                    build_box_face(
+                        &mut slices[slice as usize],
+                        block,
+                        uv_map,
+                        side,
+                        [x, cells(lower, slice), z],
+                        [1.0, cells(upper, slice) - cells(lower, slice), 1.0],
+                        scale,
+                    );
+                }
+            }
+        }
+    }
+
+    slices
+        .into_iter()
+        .enumerate()
+        .filter(|(_, layers)| layers.iter().any(|vertices| !vertices.is_empty()))
+        .map(|(slice, layers)| (slice_base(slice as i32), layers))
+        .collect()
+}
+
+// 워커 스레드에서 만들어진 LOD 메시 - 조각마다 (시작 높이, vertex)
+struct MeshedTile {
+    tile: LodTile,
+    slices: Vec<(i32, MeshLayers)>,
+}
+
+pub struct LodSlice {
+    // 조각이 시작하는 높이 (블록)
+    pub base_y: i32,
+    pub mesh: LayeredChunkMesh,
+}
+
+// 타일의 메시 조각들 (아래부터)
+pub struct LodMesh {
+    pub slices: Vec<LodSlice>,
+}
+
+impl LodMesh {
+    fn release(&mut self, arena: &mut MeshArena) {
+        for slice in &mut self.slices {
+            slice.mesh.release(arena);
+        }
+        self.slices.clear();
+    }
+}
+
+pub struct LodManager {
+    // 지금 그려야 하는 타일 - 메시가 아직 없는 타일도 들어 있다
+    tiles: Vec<LodTile>,
+    meshes: HashMap<LodTile, LodMesh>,
+    pending: HashSet<LodTile>,
+    // 메시가 준비된 타일이 덮는, 불러온 청크 기둥 - 이 기둥의 청크는 그리지 않는다
+    covered_columns: HashSet<(i32, i32)>,
+    // 마지막으로 타일을 고를 때의 카메라 청크 기둥과 불러온 기둥 수
+    selected_for: Option<((i32, i32), usize)>,
+    sender: Sender<MeshedTile>,
+    receiver: Receiver<MeshedTile>,
+}
+
+impl LodManager {
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+
+        Self {
+            tiles: Vec::new(),
+            meshes: HashMap::new(),
+            pending: HashSet::new(),
+            covered_columns: HashSet::new(),
+            selected_for: None,
+            sender,
+            receiver,
+        }
+    }
+
+    // 카메라가 다른 청크 기둥으로 옮겼거나 불러온 기둥이 바뀌면 타일을 다시 고르고,
+    // 메시가 없는 타일은 가까운 것부터 정해진 수만큼 워커에 넘긴다
+    pub fn update(
+        &mut self,
+        center: (i32, i32),
+        loaded_columns: &HashSet<(i32, i32)>,
+        generator: &Arc<WorldGenerator>,
+        workers: &WorkerPool,
+        uv_map: &Arc<UVMap>,
+        arena: &mut MeshArena,
+    ) {
+        let mut changed = false;
+        while let Ok(meshed) = self.receiver.try_recv() {
+            self.pending.remove(&meshed.tile);
+            if !self.tiles.contains(&meshed.tile) {
+                continue;
+            }
+            let lod_mesh = self
+                .meshes
+                .entry(meshed.tile)
+                .or_insert_with(|| LodMesh { slices: Vec::new() });
+            lod_mesh.release(arena);
+            for (base_y, layers) in meshed.slices {
+                let mut mesh = LayeredChunkMesh::new();
+                mesh.upload(arena, layers);
+                lod_mesh.slices.push(LodSlice { base_y, mesh });
+            }
+            changed = true;
+        }
+
+        let key = (center, loaded_columns.len());
+        if self.selected_for != Some(key) {
+            self.selected_for = Some(key);
+            self.tiles = select_tiles(center, |column| loaded_columns.contains(&column));
+            self.tiles.sort_by_key(|tile| tile.distance_squared(center));
+
+            let selected: HashSet<LodTile> = self.tiles.iter().copied().collect();
+            self.meshes.retain(|tile, lod_mesh| {
+                let keep = selected.contains(tile);
+                if !keep {
+                    lod_mesh.release(arena);
+                }
+                keep
+            });
+            changed = true;
+        }
+
+        if changed {
+            self.covered_columns = self
+                .tiles
+                .iter()
+                .filter(|tile| self.meshes.contains_key(tile))
+                .flat_map(|tile| tile.columns())
+                .filter(|column| loaded_columns.contains(column))
+                .collect();
+        }
+
+        // 청크 생성이 밀리지 않도록 워커 수만큼만 쌓는다
+        let capacity = workers.size().saturating_sub(self.pending.len());
+        let missing: Vec<LodTile> = self
+            .tiles
+            .iter()
+            .filter(|tile| !self.meshes.contains_key(tile) && !self.pending.contains(tile))
+            .take(MAX_LOD_TILES_MESHED_PER_FRAME.min(capacity))
+            .copied()
+            .collect();
+
+        for tile in missing {
+            self.pending.insert(tile);
+
+            let generator = Arc::clone(generator);
+            let uv_map = Arc::clone(uv_map);
+            let sender = self.sender.clone();
+            workers.execute(move || {
+                let slices = build_lod_mesh(&generator, &tile, &uv_map);
+                let _ = sender.send(MeshedTile { tile, slices });
+            });
+        }
+    }
+
+    pub fn covers(&self, column: (i32, i32)) -> bool {
+        self.covered_columns.contains(&column)
+    }
+
+    // 메시가 준비된 타일
+    pub fn meshed_tiles(&self) -> impl Iterator<Item = LodTile> + '_ {
+        self.tiles
+            .iter()
+            .filter(|tile| self.meshes.contains_key(tile))
+            .copied()
+    }
+
+    pub fn mesh(&self, tile: &LodTile) -> Option<&LodMesh> {
+        self.meshes.get(tile)
+    }
+
+    pub fn mesh_mut(&mut self, tile: &LodTile) -> Option<&mut LodMesh> {
+        self.meshes.get_mut(tile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_registry::BLOCKS;
+    use crate::block_texture_sides::BlockFaces;
+    use crate::mesher::{unpack_position, VERTICES_PER_QUAD, WORDS_PER_VERTEX};
+    use crate::texture_atlas::AtlasRegion;
+
+    #[test]
+    fn selected_tiles_cover_the_range_once() {
+        let center = (3, -5);
+        let is_loaded = |(x, z): (i32, i32)| {
+            let (dx, dz) = (x - center.0, z - center.1);
+            dx * dx + dz * dz <= 6 * 6
+        };
+        let tiles = select_tiles(center, is_loaded);
+        let mut coverage: HashMap<(i32, i32), usize> = HashMap::new();
+        for tile in &tiles {
+            for column in tile.columns() {
+                *coverage.entry(column).or_insert(0) += 1;
+            }
+        }
+        assert!(coverage.values().all(|&count| count == 1), "Tiles overlap");
+
+        let range = *LOD_DISTANCES.last().unwrap();
+        for z in center.1 - range..=center.1 + range {
+            for x in center.0 - range..=center.0 + range {
+                let (dx, dz) = (x - center.0, z - center.1);
+                if dx * dx + dz * dz > range * range {
+                    continue;
+                }
+
+                // 타일이 덮거나, 불러온 청크 기둥이 그 자리를 그린다
+                let covered = coverage.contains_key(&(x, z));
+                assert!(
+                    covered || is_loaded((x, z)),
+                    "Column ({x}, {z}) isn't drawn"
+                );
+            }
+        }
+
+        // 카메라에서 멀수록 큰 타일
+        for tile in &tiles {
+            let level = tile.level();
+            if level > 0 {
+                let near = LOD_DISTANCES[level - 1];
+                assert!(
+                    tile.distance_squared(center) >= near * near,
+                    "{tile:?} is too coarse"
+                );
+            }
+        }
+    }
+
+    fn test_uv_map() -> UVMap {
+        let region = |index: u32| AtlasRegion {
+            index,
+            x: 0,
+            y: 0,
+            width: 16,
+            height: 16,
+            uv: (0.0, 0.0, 1.0, 1.0),
+        };
+        (0..BLOCKS.len())
+            .map(|id| (BlockID(id as u16), BlockFaces::All(region(id as u32))))
+            .collect()
+    }
+
+    // 조각들을 다시 월드 높이로 펴서, 칸마다 윗면의 높이와 옆면(면 번호별)의 높이 합을 비교한다
+    fn check_slices(surface: &[(i32, BlockID)], tile: &LodTile) -> usize {
+        let get = |i: i32, j: i32| surface[((j + 1) * (TILE_CELLS + 2) + (i + 1)) as usize].0;
+        let scale = tile.scale as f32;
+        let slices = mesh_surface(surface, tile, &test_uv_map());
+
+        let mut tops = HashMap::new();
+        let mut walls: HashMap<(i32, i32, u32), f32> = HashMap::new();
+        for (base_y, layers) in &slices {
+            let quads = layers
+                .iter()
+                .flat_map(|v| v.chunks(WORDS_PER_VERTEX * VERTICES_PER_QUAD));
+            for quad in quads {
+                let corners: Vec<[f32; 3]> =
+                    quad.chunks(WORDS_PER_VERTEX).map(unpack_position).collect();
+                let min = |axis: usize| corners.iter().map(|c| c[axis]).fold(f32::MAX, f32::min);
+                let max = |axis: usize| corners.iter().map(|c| c[axis]).fold(f32::MIN, f32::max);
+                assert!(max(1) <= SLICE_CELLS as f32, "{tile:?}: {corners:?}");
+
+                let (i, j) = (min(0) as i32, min(2) as i32);
+                let world_y = |cells: f32| *base_y as f32 + cells * scale;
+                match (quad[0] >> 15) & 0x7 {
+                    2 => assert!(tops.insert((i, j), world_y(max(1))).is_none()),
+                    side => {
+                        // 오른쪽(0)과 앞(4) 면은 칸의 먼 쪽 경계에 있다
+                        let (i, j) = match side {
+                            0 => (i - 1, j),
+                            4 => (i, j - 1),
+                            _ => (i, j),
+                        };
+                        let height = world_y(max(1)) - world_y(min(1));
+                        *walls.entry((i, j, side)).or_insert(0.0) += height;
+                    }
+                }
+            }
+        }
+
+        for j in 0..TILE_CELLS {
+            for i in 0..TILE_CELLS {
+                let top = get(i, j) + 1;
+                assert_eq!(
+                    tops.get(&(i, j)),
+                    Some(&(top as f32)),
+                    "{tile:?} cell ({i}, {j})"
+                );
+
+                for (side, (di, dj)) in [(0, (1, 0)), (1, (-1, 0)), (4, (0, 1)), (5, (0, -1))] {
+                    let (ni, nj) = (i + di, j + dj);
+                    let mut bottom = get(ni, nj) + 1;
+                    if ni < 0 || ni >= TILE_CELLS || nj < 0 || nj >= TILE_CELLS {
+                        bottom = bottom.min(top) - LOD_SKIRT_DEPTH * tile.scale;
+                    }
+                    let expected = (top - bottom).max(0) as f32;
+                    let height = walls.get(&(i, j, side)).copied().unwrap_or(0.0);
+                    assert_eq!(height, expected, "{tile:?} cell ({i}, {j}) side {side}");
+                }
+            }
+        }
+
+        slices.len()
+    }
+
+    #[test]
+    fn lod_slices_match_generated_terrain() {
+        let generator = WorldGenerator::new(7);
+        for tile in [
+            LodTile {
+                scale: 2,
+                x: 0,
+                z: 0,
+            },
+            LodTile {
+                scale: 2,
+                x: 78,
+                z: -74,
+            },
+            LodTile {
+                scale: 8,
+                x: 40,
+                z: -64,
+            },
+        ] {
+            check_slices(&sample_surface(&generator, &tile), &tile);
+        }
+    }
+
+    #[test]
+    fn tall_lod_tiles_are_split_into_slices() {
+        // 한 타일 안에서 200블록 가까이 솟는 절벽 - 조각 하나(32블록)에는 들어가지 않는다
+        let tile = LodTile {
+            scale: 2,
+            x: 0,
+            z: 0,
+        };
+        let stone = BLOCKS.id("stone");
+        let mut surface = Vec::new();
+        for j in -1..=TILE_CELLS {
+            for i in -1..=TILE_CELLS {
+                let height = if i > 8 { 190 + j } else { 10 + i * 3 + j };
+                surface.push((height, stone));
+            }
+        }
+
+        assert!(check_slices(&surface, &tile) > 5);
+    }
+}
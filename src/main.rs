@@ -5,43 +5,72 @@ extern crate lazy_static;
 pub mod debugging;
 
 pub mod aabb;
+pub mod arena;
+pub mod biome;
+pub mod block_registry;
+pub mod block_state;
+pub mod block_storage;
 pub mod block_texture_sides;
+pub mod block_ticks;
 pub mod chunk;
 pub mod chunk_manager;
+pub mod chunk_mesh;
 pub mod constants;
 pub mod ecs;
+pub mod entities;
+pub mod explosion;
+pub mod features;
+pub mod fluids;
+pub mod frustum;
+pub mod lighting;
+pub mod lod;
+pub mod mesher;
 pub mod physics;
 pub mod raycast;
+pub mod region;
 pub mod renderer;
 pub mod shader;
-pub mod shapes;
 pub mod texture;
+pub mod texture_atlas;
 pub mod util;
+pub mod visibility;
+pub mod voxel_shape;
+pub mod worker_pool;
+pub mod world_gen;
 
 use crate::debugging::*;
 use crate::shader::{ShaderPart, ShaderProgram};
 use crate::util::forward;
 
-use crate::aabb::AABB;
+use crate::block_registry::BLOCKS;
 use crate::block_texture_sides::BlockFaces;
 use crate::chunk::BlockID;
 use crate::chunk_manager::ChunkManager;
 use crate::constants::*;
+use crate::frustum::Frustum;
 use crate::physics::{PhysicsManager, PlayerPhysicsState};
+use crate::texture_atlas::{AtlasRegion, AtlasSettings, TextureAtlasBuilder};
 use glfw::ffi::glfwSwapInterval;
 use glfw::{Action, Context, CursorMode, Key, MouseButton, WindowHint};
 use image::imageops::horizontal_gradient;
-use image::{ColorType, DynamicImage};
 use nalgebra::{clamp, Vector3};
 use nalgebra_glm::{pi, vec2, vec3, IVec3, Vec2, Vec3};
 use std::collections::HashMap;
 use std::ffi::CString;
 use std::os::raw::c_void;
+use std::sync::Arc;
 use std::time;
 
-type UVCoords = (f32, f32, f32, f32);
+type UVFaces = (
+    AtlasRegion,
+    AtlasRegion,
+    AtlasRegion,
+    AtlasRegion,
+    AtlasRegion,
+    AtlasRegion,
+);
 
-type UVFaces = (UVCoords, UVCoords, UVCoords, UVCoords, UVCoords, UVCoords);
+type UVMap = HashMap<BlockID, BlockFaces<AtlasRegion>>;
 
 pub struct InputCache {
     pub last_cursor_pos: Vec2,
@@ -68,7 +97,23 @@ impl InputCache {
     }
 }
 
-
+// 숫자 키 1~9가 가리키는 HOTBAR 칸
+fn hotbar_slot(key: Key) -> Option<usize> {
+    let keys = [
+        Key::Num1,
+        Key::Num2,
+        Key::Num3,
+        Key::Num4,
+        Key::Num5,
+        Key::Num6,
+
+
+        Key::Num7,
+        Key::Num8,
+        Key::Num9,
+    ];
+    keys.iter().position(|&k| k == key)
+}
 
 pub struct PlayerRenderState {
     pub rotation: Vec3,
@@ -164,174 +209,59 @@ fn main() {
 
     // 각 블록들의 텍스쳐 제작
     // Generate texture atlas
+    let mut builder = TextureAtlasBuilder::new(AtlasSettings {
+        padding: TEXTURE_ATLAS_PADDING,
+        max_mip_levels: TEXTURE_ATLAS_MIP_LEVELS,
+        max_size: TEXTURE_ATLAS_MAX_SIZE,
+    });
     let mut texture_map: HashMap<BlockID, BlockFaces<&str>> = HashMap::new();
-    texture_map.insert(BlockID::Dirt, BlockFaces::All("blocks/dirt.png"));
-    texture_map.insert(
-        BlockID::GrassBlock,
-        BlockFaces::Sides {
-            sides: "blocks/grass_block_side.png",
-            top: "blocks/grass_block_top.png",
-            bottom: "blocks/dirt.png",
-        },
-    );
-    texture_map.insert(
-        BlockID::Cobblestone,
-        BlockFaces::All("blocks/cobblestone.png"),
-    );
-    texture_map.insert(BlockID::Obsidian, BlockFaces::All("blocks/obsidian.png"));
-    texture_map.insert(
-        BlockID::OakLog,
-        BlockFaces::Sides {
-            sides: "blocks/oak_log.png",
-            top: "blocks/oak_log_top.png",
-            bottom: "blocks/oak_log_top.png",
-        },
-    );
-    texture_map.insert(BlockID::OakLeaves, BlockFaces::All("blocks/oak_leaves.png"));
-    texture_map.insert(BlockID::Debug, BlockFaces::All("blocks/debug.png"));
-    texture_map.insert(BlockID::Debug2, BlockFaces::All("blocks/debug2.png"));
-
-    let mut atlas = 0;
-    gl_call!(gl::CreateTextures(gl::TEXTURE_2D, 1, &mut atlas));
-    gl_call!(gl::TextureParameteri(
-        atlas,
-        gl::TEXTURE_MIN_FILTER,
-        gl::NEAREST_MIPMAP_NEAREST as i32
-    ));
-    gl_call!(gl::TextureParameteri(
-        atlas,
-        gl::TEXTURE_MAG_FILTER,
-        gl::NEAREST as i32
-    ));
-    gl_call!(gl::TextureStorage2D(
-        atlas,
-        1,
-        gl::RGBA8,
-        TEXTURE_ATLAS_SIZE as i32,
-        TEXTURE_ATLAS_SIZE as i32,
-    ));
-
-    let mut uv_map = HashMap::<BlockID, BlockFaces<UVCoords>>::new();
-    let mut x = 0;
-    let mut y = 0;
-
-    let load_image = |texture_path: &str| {
-        let img = image::open(texture_path);
-        let img = match img {
-            Ok(img) => img.flipv(),
-            Err(err) => panic!("Filename: {texture_path}, error: {}", err.to_string()),
-        };
-        match img.color() {
-            ColorType::Rgba8 => {}
-            _ => panic!("Texture format not supported"),
-        };
-
-        img
-    };
-
-    let mut blit_image = |img: &mut DynamicImage| {
-        let (dest_x, dest_y) = (x, y);
-
-        gl_call!(gl::TextureSubImage2D(
-            atlas,
-            0,
-            dest_x as i32,
-            dest_y as i32,
-            img.width() as i32,
-            img.height() as i32,
-            gl::RGBA,
-            gl::UNSIGNED_BYTE,
-            img.as_bytes().as_ptr() as *mut c_void
-        ));
-
-        x += BLOCK_TEXTURE_SIZE;
-
-        if x >= TEXTURE_ATLAS_SIZE {
-            x = 0;
-            y += BLOCK_TEXTURE_SIZE;
+    for (block, definition) in BLOCKS.iter() {
+        if let Some(faces) = &definition.faces {
+            for path in faces.values() {
+                builder.add_file(path).unwrap_or_else(|err| panic!("{err}"));
+            }
+            texture_map.insert(block, faces.map(|path| path.as_str()));
         }
+    }
 
-        let (dest_x, dest_y) = (dest_x as f32, dest_y as f32);
-        (
-            dest_x / TEXTURE_ATLAS_SIZE as f32,
-            dest_y / TEXTURE_ATLAS_SIZE as f32,
-            (dest_x + BLOCK_TEXTURE_SIZE as f32) / TEXTURE_ATLAS_SIZE as f32,
-            (dest_y + BLOCK_TEXTURE_SIZE as f32) / TEXTURE_ATLAS_SIZE as f32,
-        )
-    };
-
-    for (block, faces) in texture_map {
-        match faces {
-            BlockFaces::All(all) => {
-                let mut img = load_image(all);
-                let uv = blit_image(&mut img);
-                uv_map.insert(block, BlockFaces::All(uv));
-            }
-            BlockFaces::Sides { sides, top, bottom } => {
-                let mut img = load_image(sides);
-                let uv_sides = blit_image(&mut img);
-
-                let mut img = load_image(top);
-                let uv_top = blit_image(&mut img);
-
-                let mut img = load_image(bottom);
-                let uv_bottom = blit_image(&mut img);
-
-                uv_map.insert(
-                    block,
-                    BlockFaces::Sides {
-                        sides: uv_sides,
-                        top: uv_top,
-                        bottom: uv_bottom,
-                    },
-                );
-            }
-            BlockFaces::Each {
-                top,
-                bottom,
-                front,
-                back,
-                left,
-                right,
-            } => {
-                let mut img = load_image(top);
-                let uv_top = blit_image(&mut img);
-
-                let mut img = load_image(bottom);
-                let uv_bottom = blit_image(&mut img);
-
-                let mut img = load_image(front);
-                let uv_front = blit_image(&mut img);
-
-                let mut img = load_image(back);
-                let uv_back = blit_image(&mut img);
-
-                let mut img = load_image(left);
-                let uv_left = blit_image(&mut img);
-
-                let mut img = load_image(right);
-                let uv_right = blit_image(&mut img);
-
-                uv_map.insert(
-                    block,
-                    BlockFaces::Each {
-                        top: uv_top,
-                        bottom: uv_bottom,
-                        front: uv_front,
-                        back: uv_back,
-                        left: uv_left,
-                        right: uv_right,
-                    },
-                );
-            }
+    let atlas = builder.build().unwrap_or_else(|err| panic!("{err}"));
+    if EXPORT_TEXTURE_ATLAS {
+        if let Err(err) = atlas.export(TEXTURE_ATLAS_EXPORT_PNG, TEXTURE_ATLAS_EXPORT_JSON) {
+            eprintln!("Failed to export the texture atlas: {err}");
         }
     }
 
+    let uv_map: UVMap = texture_map
+        .into_iter()
+        .map(|(block, faces)| (block, faces.map(|path| atlas.region(path).unwrap())))
+        .collect();
+
+    // 메시를 만드는 워커 스레드들과 공유한다
+    let uv_map = Arc::new(uv_map);
+
+    let atlas_texture = texture::create_atlas_texture(&atlas);
+    let atlas_regions = texture::create_atlas_region_buffer(&atlas);
     gl_call!(gl::ActiveTexture(gl::TEXTURE0 + 0));
-    gl_call!(gl::BindTexture(gl::TEXTURE_2D, atlas));
+    gl_call!(gl::BindTexture(gl::TEXTURE_2D, atlas_texture));
+    gl_call!(gl::BindBufferBase(
+        gl::SHADER_STORAGE_BUFFER,
+        0,
+        atlas_regions
+    ));
 
     let mut chunk_manager = ChunkManager::new();
-    chunk_manager.simplex();
+    // 플레이어가 처음 서 있을 청크가 생성될 때까지 기다린다
+    let spawn_position = physics_manager.get_current_state().position;
+    let spawn_chunk =
+        ChunkManager::get_chunk_coords(spawn_position.x, spawn_position.y, spawn_position.z);
+    while !chunk_manager.is_column_loaded(spawn_chunk.0, spawn_chunk.2) {
+        chunk_manager.update_loaded_chunks(spawn_chunk);
+        std::thread::sleep(time::Duration::from_millis(1));
+    }
+
+    let mut placed_block = BLOCKS.id(HOTBAR[0]);
+    // 그린 청크 수를 창 제목에 보여준 마지막 시간
+    let mut last_stats_report = time::Instant::now();
 
     let mut input_cache = InputCache::default();
     let mut prev_cursor_pos = (0.0, 0.0);
@@ -364,6 +294,12 @@ fn main() {
 
                 glfw::WindowEvent::Key(key, _, action, _) => {
                     input_cache.key_states.insert(key, action);
+
+                    if action == Action::Press {
+                        if let Some(slot) = hotbar_slot(key) {
+                            placed_block = BLOCKS.id(HOTBAR[slot]);
+                        }
+                    }
                 }
 
                 glfw::WindowEvent::MouseButton(button, Action::Press, _) => {
@@ -371,8 +307,7 @@ fn main() {
                     let get_voxel = |x: i32, y: i32, z: i32| {
                         chunk_manager
                             .get_block(x, y, z)
-                            .filter(|&block| block != BlockID::Air)
-                            .and_then(|_| Some((x, y, z)))
+                            .map(|block| ((x, y, z), block.definition().selection_shape()))
                     };
 
                     let player = physics_manager.get_current_state();
@@ -383,18 +318,41 @@ fn main() {
                         REACH_DISTANCE,
                     );
 
-                    if let Some(((x, y, z), normal)) = hit {
+                    if let Some(((x, y, z), normal, hit_position)) = hit {
                         if button == MouseButton::Button1 {
-                            chunk_manager.set_block(x, y, z, BlockID::Air)
+                            chunk_manager.set_block(x, y, z, BlockID::AIR)
                         } else if button == MouseButton::Button2 {
-                            let near = IVec3::new(x, y, z) + normal;
+                            // TNT에 오른쪽 클릭을 하면 블록을 놓는 대신 불을 붙인다
+                            if explosion::ignite(&mut chunk_manager, x, y, z) {
+                                continue;
+                            }
 
-                            if !player.aabb.intersects(&get_block_aabb(&vec3(
-                                near.x as f32,
-                                near.y as f32,
-                                near.z as f32,
-                            ))) {
-                                chunk_manager.set_block(near.x, near.y, near.z, BlockID::Debug2);
+                            // 쌓이는 블록(눈)은 같은 블록 위에 한 단계 더 쌓는다
+                            let target = chunk_manager.get_block(x, y, z).unwrap();
+                            if let Some(stacked) = block_state::stacked_level(target, placed_block)
+                            {
+                                chunk_manager.set_block(x, y, z, stacked);
+                                continue;
+                            }
+
+                            let near = IVec3::new(x, y, z) + normal;
+                            // 맞은 면과 보는 방향에 따라 통나무의 축, 계단의 방향 등을 정한다
+                            let block = block_state::placement_state(
+                                placed_block,
+                                &normal,
+                                &hit_position,
+                                &forward,
+                            );
+
+                            // 놓을 블록의 충돌 모양이 플레이어와 겹치면 놓지 않는다
+                            let overlaps_player = block
+                                .definition()
+                                .collision_shape()
+                                .at(near.x, near.y, near.z)
+                                .any(|aabb| player.aabb.intersects(&aabb));
+
+                            if !overlaps_player {
+                                chunk_manager.set_block(near.x, near.y, near.z, block);
                                 println!("Put block at {} {} {}", near.x, near.y, near.z);
                             }
                         }
@@ -415,7 +373,7 @@ fn main() {
 
         let player = physics_manager.get_current_state();
 
-        use crate::physics::get_block_aabb;
+        use crate::physics::move_and_collide;
         use num_traits::identities::Zero;
 
         let render_state =
@@ -434,6 +392,7 @@ fn main() {
                     directional_acceleration += forward(&rotation);
                 }
 
+
                 if input_cache.is_key_pressed(Key::S) {
                     directional_acceleration -= forward(&rotation);
                 }
@@ -446,13 +405,16 @@ fn main() {
                     directional_acceleration += forward(&rotation).cross(&Vector3::y());
                 }
 
-
                 if directional_acceleration.norm_squared() != 0.0 {
                     let directional_acceleration =
                         directional_acceleration.normalize().scale(HORIZONTAL_ACCERLATION);
                     player.acceleration = directional_acceleration;
                 }
 
+                // 폭발 등으로 걷는 속도보다 빨라졌으면 그 속도는 서서히 줄어든다
+                let previous_speed = vec2(player.velocity.x, player.velocity.z).magnitude();
+                let speed_limit = WALKING_SPEED.max(previous_speed * (1.0 - AIR_DRAG * dt));
+
                 player.acceleration.y = GRAVITY;
                 player.velocity += player.acceleration * dt;
 
@@ -460,8 +422,8 @@ fn main() {
 
                 let mag = horizontal.magnitude();
 
-                if mag > WALKING_SPEED {
-                    horizontal = horizontal.scale(WALKING_SPEED / mag);
+                if mag > speed_limit {
+                    horizontal = horizontal.scale(speed_limit / mag);
                 }
 
                 // Vertical
@@ -473,131 +435,12 @@ fn main() {
                 player.velocity.x = horizontal.x;
                 player.velocity.z = horizontal.y;
 
-                let mut is_player_on_ground = false;
-
-                let separated_axis = &[
-                    vec3(player.velocity.x, 0.0, 0.0),
-                    vec3(0.0, player.velocity.y, 0.0),
-                    vec3(0.0, 0.0, player.velocity.z),
-                ];
-
-                for v in separated_axis {
-                    player.aabb.translate(&(v * dt));
-
-                    let player_mins = &player.aabb.mins;
-                    let player_maxs = &player.aabb.maxs;
-
-                    let block_min = vec3(
-                        player_mins.x.floor() as i32,
-                        player_mins.y.floor() as i32,
-                        player_mins.z.floor() as i32,
-                    );
-                    let block_max = vec3(
-                        player_maxs.x.floor() as i32,
-                        player_maxs.y.floor() as i32,
-                        player_maxs.z.floor() as i32,
-                    );
-
-                    let mut blocks_collided = None;
-
-                    'outer: for y in block_min.y..=block_max.y {
-                        for z in block_min.z..=block_max.z {
-                            for x in block_min.x..=block_max.x {
-                                if let Some(block) = chunk_manager.get_block(x, y, z) {
-                                    if block.is_air() {
-                                        continue;
-                                    }
-
-                                    let block_aabb =
-                                        get_block_aabb(&vec3(x as f32, y as f32, z as f32));
-
-                                    if player.aabb.intersects(&block_aabb) {
-                                        blocks_collided = Some(vec3(x as f32, y as f32, z as f32));
-                                        break 'outer;
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    // 충돌에 대한 반응
-                    if let Some(block_collided) = blocks_collided {
-                        let block_aabb = get_block_aabb(&block_collided);
-
-                        if !v.x.is_zero() {
-                            if v.x < 0.0 {
-                                player.aabb = AABB::new(
-                                    vec3(block_aabb.maxs.x, player.aabb.mins.y, player.aabb.mins.z),
-                                    vec3(
-                                        block_aabb.maxs.x + PLAYER_WIDTH,
-                                        player.aabb.maxs.y,
-                                        player.aabb.maxs.z,
-                                    ),
-                                )
-                            } else {
-                                player.aabb = AABB::new(
-                                    vec3(
-                                        block_aabb.mins.x - PLAYER_WIDTH,
-                                        player.aabb.mins.y,
-                                        player.aabb.mins.z,
-                                    ),
-                                    vec3(block_aabb.mins.x, player.aabb.maxs.y, player.aabb.maxs.z),
-                                )
-                            }
-
-                            player.velocity.x = 0.0;
-                        }
-
-                        if !v.y.is_zero() {
-                            if v.y < 0.0 {
-                                player.aabb = AABB::new(
-                                    vec3(player.aabb.mins.x, block_aabb.maxs.y, player.aabb.mins.z),
-                                    vec3(
-                                        player.aabb.maxs.x,
-                                        block_aabb.maxs.y + PLAYER_HEIGHT,
-                                        player.aabb.maxs.z,
-                                    ),
-                                );
-                                is_player_on_ground = true;
-                            } else {
-                                player.aabb = AABB::new(
-                                    vec3(
-                                        player.aabb.mins.x,
-                                        block_aabb.mins.y - PLAYER_HEIGHT,
-                                        player.aabb.mins.z,
-                                    ),
-                                    vec3(player.aabb.maxs.x, block_aabb.mins.y, player.aabb.maxs.z),
-                                )
-                            }
-
-                            player.velocity.y = 0.0;
-                        }
-
-                        if !v.z.is_zero() {
-                            if v.z < 0.0 {
-                                player.aabb = AABB::new(
-                                    vec3(player.aabb.mins.x, player.aabb.mins.y, block_aabb.maxs.z),
-                                    vec3(
-                                        player.aabb.maxs.x,
-                                        player.aabb.maxs.y,
-                                        block_aabb.maxs.z + PLAYER_WIDTH,
-                                    ),
-                                )
-                            } else {
-                                player.aabb = AABB::new(
-                                    vec3(
-                                        player.aabb.mins.x,
-                                        player.aabb.mins.y,
-                                        block_aabb.mins.z - PLAYER_WIDTH,
-                                    ),
-                                    vec3(player.aabb.maxs.x, player.aabb.maxs.y, block_aabb.mins.z),
-                                )
-                            }
-
-                            player.velocity.z = 0.0;
-                        }
-                    }
-                }
+                let is_player_on_ground =
+                    move_and_collide(&mut player.aabb, &mut player.velocity, dt, &|x, y, z| {
+                        chunk_manager
+                            .get_block(x, y, z)
+                            .map(|block| block.definition().collision_shape())
+                    });
 
                 player.position.x = player.aabb.mins.x + PLAYER_HALF_WIDTH;
                 player.position.y = player.aabb.mins.y;
@@ -639,6 +482,21 @@ fn main() {
         );
 
         let projection_matrix = nalgebra_glm::perspective(1.0, pi::<f32>() / 2.0, NEAR_PLANE, FAR_PLANE);
+        let frustum = Frustum::from_matrix(&(projection_matrix * view_matrix));
+
+        chunk_manager.update_loaded_chunks(ChunkManager::get_chunk_coords(
+            camera_position.x,
+            camera_position.y,
+            camera_position.z,
+        ));
+        // 블록 업데이트(유체, 풀, 잎 등)는 물리 틱에 맞춰 월드 틱 단위로 진행한다
+        chunk_manager.update_ticks(physics_manager.ticks);
+
+        for explosion in chunk_manager.explosions.drain(..) {
+            let player = physics_manager.get_current_state();
+            let center = (player.aabb.mins + player.aabb.maxs) / 2.0;
+            player.velocity += explosion::knockback(&explosion, &center);
+        }
 
         chunk_manager.rebuild_dirty_chunks(&uv_map);
 
@@ -652,9 +510,31 @@ fn main() {
         gl_call!(gl::ClearColor(r, g, b, a));
         gl_call!(gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT));
 
-        chunk_manager.render_loaded_chunks(&mut program);
+        chunk_manager.render_loaded_chunks(&mut program, &frustum);
+        chunk_manager.render_entities(&mut program, &uv_map);
+        chunk_manager.render_translucent_chunks(&mut program, &camera_position);
+
+        if last_stats_report.elapsed() >= time::Duration::from_secs_f32(RENDER_STATS_INTERVAL) {
+            let stats = chunk_manager.render_stats;
+            window.set_title(&format!(
+                "{WINDOW_NAME} - chunks drawn: {}, culled: {}, occluded: {}, lod tiles: {}, \
+                 mesh arena: {}/{} quads ({:.0}% fragmented)",
+                stats.drawn,
+                stats.culled,
+                stats.occluded,
+                stats.lod_tiles,
+                stats.arena_used,
+                stats.arena_capacity,
+                stats.arena_fragmentation * 100.0
+            ));
+            last_stats_report = time::Instant::now();
+        }
 
         // 프론트 버퍼와 백 버퍼 교체 - 프리징 방지
         window.swap_buffers();
     }
+
+    if let Err(err) = chunk_manager.save() {
+        eprintln!("Failed to save the world: {err}");
+    }
 }
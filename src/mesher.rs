@@ -0,0 +1,885 @@
+use crate::block_state::Rotation;
+use crate::block_texture_sides::get_uv_every_side;
+use crate::chunk::BlockID;
+use crate::chunk_manager::{Sides, CHUNK_SIZE};
+use crate::fluids;
+use crate::lighting::{self, MAX_LIGHT};
+use crate::texture_atlas::AtlasRegion;
+use crate::{UVFaces, UVMap};
+use serde::Deserialize;
+
+pub const PADDED_SIZE: i32 = CHUNK_SIZE as i32 + 2;
+
+// vertex 하나는 u32 3개 (diffuse.vert가 푼다)
+// word 0: x, y, z의 정수 부분 (각 5비트) | 면 (3) | 사각형의 모서리 (2) | AO (2) | 하늘빛 (4) | 블록 빛 (4)
+// word 1: 아틀라스 타일 번호 (12, AtlasRegion::index) | x, y, z의 1/16 단위 소수 부분 (각 4비트)
+// word 2: 블록 단위 texture 좌표 u, v (각 16비트, 부호 있는 1/16 단위)
+pub const WORDS_PER_VERTEX: usize = 3;
+// 사각형 하나는 vertex 4개 - 삼각형은 모든 메시가 함께 쓰는 index buffer로 나눈다
+pub const VERTICES_PER_QUAD: usize = 4;
+
+// 위치와 texture 좌표의 소수 부분 단위 (텍스쳐 픽셀 하나)
+const SUBDIVISIONS: f32 = 16.0;
+
+// 모서리를 가리는 블록이 없을 때의 AO 값 (0 ~ 3)
+const MAX_AO: u8 = 3;
+
+// 블록을 그리는 단계 (blocks.json의 "render_layer")
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderLayer {
+    Opaque,
+    // 텍스쳐의 투명한 부분을 버린다 (잎, 꽃)
+    Cutout,
+    // 뒤가 비쳐 보인다 (물)
+    Translucent,
+}
+
+pub const RENDER_LAYERS: [RenderLayer; 3] = [
+    RenderLayer::Opaque,
+    RenderLayer::Cutout,
+    RenderLayer::Translucent,
+];
+
+// 렌더 단계별 vertex (RenderLayer 순서)
+pub type MeshLayers = [Vec<u32>; 3];
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MeshingStrategy {
+    // 같은 텍스쳐의 인접한 면을 큰 사각형으로 합친다
+    Greedy,
+    // 보이는 면마다 사각형 하나
+    PerFace,
+}
+
+// 면 하나의 모양 (Sides와 같은 순서: right, left, top, bottom, front, back)
+struct FaceShape {
+    normal: [i32; 3],
+    u_axis: usize,
+    v_axis: usize,
+    // 정육면체의 모서리와 텍스쳐 좌표 (밖에서 볼 때 반시계 방향)
+    corners: [([f32; 3], [f32; 2]); 4],
+}
+
+#[rustfmt::skip]
+const FACES: [FaceShape; 6] = [
+    // right
+    FaceShape { normal: [1, 0, 0], u_axis: 2, v_axis: 1, corners: [
+        ([1.0, 0.0, 1.0], [0.0, 0.0]), ([1.0, 0.0, 0.0], [1.0, 0.0]),
+        ([1.0, 1.0, 0.0], [1.0, 1.0]), ([1.0, 1.0, 1.0], [0.0, 1.0]),
+    ]},
+    // left
+    FaceShape { normal: [-1, 0, 0], u_axis: 2, v_axis: 1, corners: [
+        ([0.0, 0.0, 0.0], [0.0, 0.0]), ([0.0, 0.0, 1.0], [1.0, 0.0]),
+        ([0.0, 1.0, 1.0], [1.0, 1.0]), ([0.0, 1.0, 0.0], [0.0, 1.0]),
+    ]},
+    // top
+    FaceShape { normal: [0, 1, 0], u_axis: 0, v_axis: 2, corners: [
+        ([0.0, 1.0, 1.0], [0.0, 0.0]), ([1.0, 1.0, 1.0], [1.0, 0.0]),
+        ([1.0, 1.0, 0.0], [1.0, 1.0]), ([0.0, 1.0, 0.0], [0.0, 1.0]),
+    ]},
+    // bottom
+    FaceShape { normal: [0, -1, 0], u_axis: 0, v_axis: 2, corners: [
+        ([0.0, 0.0, 0.0], [0.0, 0.0]), ([1.0, 0.0, 0.0], [1.0, 0.0]),
+        ([1.0, 0.0, 1.0], [1.0, 1.0]), ([0.0, 0.0, 1.0], [0.0, 1.0]),
+    ]},
+    // front
+    FaceShape { normal: [0, 0, 1], u_axis: 0, v_axis: 1, corners: [
+        ([0.0, 0.0, 1.0], [0.0, 0.0]), ([1.0, 0.0, 1.0], [1.0, 0.0]),
+        ([1.0, 1.0, 1.0], [1.0, 1.0]), ([0.0, 1.0, 1.0], [0.0, 1.0]),
+    ]},
+    // back
+    FaceShape { normal: [0, 0, -1], u_axis: 0, v_axis: 1, corners: [
+        ([1.0, 0.0, 0.0], [0.0, 0.0]), ([0.0, 0.0, 0.0], [1.0, 0.0]),
+        ([0.0, 1.0, 0.0], [1.0, 1.0]), ([1.0, 1.0, 0.0], [0.0, 1.0]),
+    ]},
+];
+
+// 면 위의 점을 그 면의 기본 텍스쳐 좌표로 (모서리 0이 (0, 0), 1이 (1, 0), 3이 (0, 1))
+fn uv_on_face(face: &FaceShape, p: [f32; 3]) -> [f32; 2] {
+    let origin = face.corners[0].0;
+    let (u_end, v_end) = (face.corners[1].0, face.corners[3].0);
+    let dot = |end: [f32; 3]| {
+        (0..3)
+            .map(|i| (p[i] - origin[i]) * (end[i] - origin[i]))
+            .sum()
+    };
+    [dot(u_end), dot(v_end)]
+}
+
+// 면의 텍스쳐 좌표를 돌린 블록의 원래 면의 텍스쳐 좌표로 바꾼다 (offset + u * uv.x + v * uv.y)
+#[derive(Copy, Clone)]
+struct UVTransform {
+    offset: [f32; 2],
+    u: [f32; 2],
+    v: [f32; 2],
+}
+
+impl UVTransform {
+    const IDENTITY: UVTransform = UVTransform {
+        offset: [0.0, 0.0],
+        u: [1.0, 0.0],
+        v: [0.0, 1.0],
+    };
+
+    fn apply(&self, [u, v]: [f32; 2]) -> [f32; 2] {
+        [
+            self.offset[0] + self.u[0] * u + self.v[0] * v,
+            self.offset[1] + self.u[1] * u + self.v[1] * v,
+        ]
+    }
+}
+
+// 상태에 따라 돌아간 블록의 면 side에 원래 어느 면의 텍스쳐가 어떤 방향으로 붙는지
+fn face_texture(block: BlockID, side: usize) -> (usize, UVTransform) {
+    let rotation = match block.definition().block_state {
+        Some(state) => state.value.rotation(),
+        None => return (side, UVTransform::IDENTITY),
+    };
+    if rotation == Rotation::IDENTITY {
+        return (side, UVTransform::IDENTITY);
+    }
+
+    let inverse = rotation.inverse();
+    let face = &FACES[side];
+    let source_normal = inverse.apply(face.normal);
+    let source_side = FACES
+        .iter()
+        .position(|source| source.normal == source_normal)
+        .unwrap();
+
+    let source_uv = |p: [f32; 3]| uv_on_face(&FACES[source_side], inverse.apply_point(p));
+    let offset = source_uv(face.corners[0].0);
+    let (u_end, v_end) = (source_uv(face.corners[1].0), source_uv(face.corners[3].0));
+
+    let transform = UVTransform {
+        offset,
+        u: [u_end[0] - offset[0], u_end[1] - offset[1]],
+        v: [v_end[0] - offset[0], v_end[1] - offset[1]],
+    };
+    (source_side, transform)
+}
+
+// 꽉 찬 블록이 아닌 모양(반 블록, 계단 등)은 합치지 않고 상자마다 따로 그린다
+fn has_custom_shape(block: BlockID) -> bool {
+    block.definition().shape.is_some()
+}
+
+// Sides 순서의 면 번호로 UVFaces에서 텍스쳐 영역을 꺼낸다
+fn face_uv((front, back, top, bottom, left, right): UVFaces, side: usize) -> AtlasRegion {
+    [right, left, top, bottom, front, back][side]
+}
+
+fn pack_vertex(
+    position: [f32; 3],
+    uv: [f32; 2],
+    tile: u32,
+    side: usize,
+    corner: usize,
+    light: u8,
+    ao: u8,
+) -> [u32; 3] {
+    let fixed = position.map(|p| (p * SUBDIVISIONS).round());
+    // 5비트 정수 + 4비트 소수 - 넘치면 다른 위치에 그려진다
+    debug_assert!(
+        fixed.iter().all(|p| (0.0..=511.0).contains(p)),
+        "Vertex position {position:?} doesn't fit in a packed vertex"
+    );
+    let fixed = fixed.map(|p| p.clamp(0.0, 511.0) as u32);
+    let whole = fixed.map(|p| p >> 4);
+    let fraction = fixed.map(|p| p & 0xF);
+    let [u, v] = uv.map(|c| (c * SUBDIVISIONS).round() as i16 as u16 as u32);
+
+    [
+        whole[0]
+            | whole[1] << 5
+            | whole[2] << 10
+            | (side as u32) << 15
+            | (corner as u32) << 18
+            | (ao as u32) << 20
+            | (lighting::sky_light(light) as u32) << 22
+            | (lighting::block_light(light) as u32) << 26,
+        tile | fraction[0] << 12 | fraction[1] << 16 | fraction[2] << 20,
+        u | v << 16,
+    ]
+}
+
+// pack_vertex로 묶은 위치를 다시 꺼낸다
+pub fn unpack_position(vertex: &[u32]) -> [f32; 3] {
+    [0, 1, 2].map(|axis| {
+        let whole = (vertex[0] >> (5 * axis)) & 0x1F;
+        let fraction = (vertex[1] >> (12 + 4 * axis)) & 0xF;
+        whole as f32 + fraction as f32 / SUBDIVISIONS
+    })
+}
+
+// 청크 하나와 이웃 청크의 경계 블록 한 겹을 복사해 둔 것 (워커 스레드에서 메시를 만든다)
+#[derive(Clone)]
+pub struct PaddedChunk {
+    blocks: Vec<BlockID>,
+    light: Vec<u8>,
+}
+
+impl PaddedChunk {
+    pub fn new() -> Self {
+        let volume = (PADDED_SIZE * PADDED_SIZE * PADDED_SIZE) as usize;
+
+        Self {
+            blocks: vec![BlockID::AIR; volume],
+            light: vec![lighting::pack_light(MAX_LIGHT, 0); volume],
+        }
+    }
+
+    // x, y, z는 청크 안 좌표 (-1..=16)
+    #[inline]
+    fn index(x: i32, y: i32, z: i32) -> usize {
+        (((y + 1) * PADDED_SIZE + (z + 1)) * PADDED_SIZE + (x + 1)) as usize
+    }
+
+    #[inline]
+    pub fn get(&self, x: i32, y: i32, z: i32) -> BlockID {
+        self.blocks[PaddedChunk::index(x, y, z)]
+    }
+
+    #[inline]
+    pub fn set(&mut self, x: i32, y: i32, z: i32, block: BlockID) {
+        self.blocks[PaddedChunk::index(x, y, z)] = block;
+    }
+
+    #[inline]
+    pub fn get_light(&self, x: i32, y: i32, z: i32) -> u8 {
+        self.light[PaddedChunk::index(x, y, z)]
+    }
+
+    #[inline]
+    pub fn set_light(&mut self, x: i32, y: i32, z: i32, light: u8) {
+        self.light[PaddedChunk::index(x, y, z)] = light;
+    }
+
+    // 면의 밝기는 그 면이 바라보는 블록의 빛으로 정한다
+    fn face_light(&self, x: i32, y: i32, z: i32, face: &FaceShape) -> u8 {
+        self.get_light(x + face.normal[0], y + face.normal[1], z + face.normal[2])
+    }
+
+    // 면의 네 모서리마다, 면 앞쪽에서 그 모서리에 닿는 블록 3개(옆 2개 + 대각선 1개)로 AO를 구한다
+    fn face_ao(&self, x: i32, y: i32, z: i32, face: &FaceShape) -> [u8; 4] {
+        let front = [x + face.normal[0], y + face.normal[1], z + face.normal[2]];
+        let occludes = |offset: [i32; 3]| {
+            !self
+                .get(
+                    front[0] + offset[0],
+                    front[1] + offset[1],
+                    front[2] + offset[2],
+                )
+                .is_transparent()
+        };
+
+        let mut ao = [MAX_AO; 4];
+        for (corner, (position, _)) in face.corners.iter().enumerate() {
+            let direction = |axis: usize| if position[axis] > 0.5 { 1 } else { -1 };
+
+            let mut side_u = [0; 3];
+            side_u[face.u_axis] = direction(face.u_axis);
+            let mut side_v = [0; 3];
+            side_v[face.v_axis] = direction(face.v_axis);
+            let mut diagonal = side_u;
+            diagonal[face.v_axis] = side_v[face.v_axis];
+
+            let (side_u, side_v, diagonal) =
+                (occludes(side_u), occludes(side_v), occludes(diagonal));
+            ao[corner] = if side_u && side_v {
+                0
+            } else {
+                MAX_AO - side_u as u8 - side_v as u8 - diagonal as u8
+            };
+        }
+
+        ao
+    }
+}
+
+pub fn get_active_sides_of_block(chunk: &PaddedChunk, x: i32, y: i32, z: i32) -> Sides {
+    let is_open = |x: i32, y: i32, z: i32| chunk.get(x, y, z).is_transparent();
+
+    let right = is_open(x + 1, y, z);
+    let left = is_open(x - 1, y, z);
+    let top = is_open(x, y + 1, z);
+    let bottom = is_open(x, y - 1, z);
+    let front = is_open(x, y, z + 1);
+    let back = is_open(x, y, z - 1);
+
+    [right, left, top, bottom, front, back]
+}
+
+pub fn build_mesh(chunk: &PaddedChunk, uv_map: &UVMap, strategy: MeshingStrategy) -> MeshLayers {
+    match strategy {
+        MeshingStrategy::Greedy => build_greedy_mesh(chunk, uv_map),
+        MeshingStrategy::PerFace => build_per_face_mesh(chunk, uv_map),
+    }
+}
+
+// 보이는 면마다 사각형 하나(vertex 4개)를 만든다 - 삼각형은 공유 index buffer로 나눈다
+pub fn build_per_face_mesh(chunk: &PaddedChunk, uv_map: &UVMap) -> MeshLayers {
+    let size = CHUNK_SIZE as i32;
+    let mut layers = MeshLayers::default();
+
+    for y in 0..size {
+        for z in 0..size {
+            for x in 0..size {
+                let block = chunk.get(x, y, z);
+                if block.is_fluid() || has_custom_shape(block) {
+                    continue;
+                }
+                let uvs = match uv_map.get(&block) {
+                    Some(&uvs) => get_uv_every_side(uvs),
+                    None => continue,
+                };
+
+                let vertices = &mut layers[block.definition().render_layer() as usize];
+                let active_sides = get_active_sides_of_block(chunk, x, y, z);
+                for (side, face) in FACES.iter().enumerate() {
+                    if active_sides[side] {
+                        let (texture_side, transform) = face_texture(block, side);
+                        write_quad(
+                            vertices,
+                            side,
+                            [x as f32, y as f32, z as f32],
+                            [1.0; 3],
+                            face_uv(uvs, texture_side),
+                            transform,
+                            chunk.face_light(x, y, z, face),
+                            chunk.face_ao(x, y, z, face),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    build_shaped_mesh(chunk, uv_map, &mut layers);
+    build_fluid_mesh(chunk, uv_map, &mut layers);
+    layers
+}
+
+// 청크 밖에서 따로 그리는 블록 하나 (떨어지는 블록, 아이템) - (0, 0, 0)부터 크기 1의 여섯 면
+pub fn build_block_mesh(vertices: &mut Vec<u32>, block: BlockID, uv_map: &UVMap, light: u8) {
+    let uvs = match uv_map.get(&block) {
+        Some(&uvs) => get_uv_every_side(uvs),
+        None => return,
+    };
+
+    for (side, face) in FACES.iter().enumerate() {
+        let positions = face.corners.map(|(position, _)| position);
+        let corner_uvs = face.corners.map(|(_, uv)| uv);
+
+        write_corners(
+            vertices,
+            side,
+            positions,
+            corner_uvs,
+            face_uv(uvs, side),
+            light,
+            [MAX_AO; 4],
+        );
+    }
+}
+
+// 블록 데이터 없이 그리는 상자 (base..base + extent)의 면 하나 (LOD 지형) - 하늘빛을 다 받는다
+pub fn build_box_face(
+    layers: &mut MeshLayers,
+    block: BlockID,
+    uv_map: &UVMap,
+    side: usize,
+    base: [f32; 3],
+    extent: [f32; 3],
+    scale: f32,
+) {
+    let uvs = match uv_map.get(&block) {
+        Some(&uvs) => get_uv_every_side(uvs),
+        None => return,
+    };
+
+    let face = &FACES[side];
+    let positions = face
+        .corners
+        .map(|(position, _)| [0, 1, 2].map(|axis| base[axis] + position[axis] * extent[axis]));
+    let corner_uvs = face.corners.map(|(_, uv)| {
+        [
+            uv[0] * extent[face.u_axis] * scale,
+            uv[1] * extent[face.v_axis] * scale,
+        ]
+    });
+
+    write_corners(
+        &mut layers[block.definition().render_layer() as usize],
+        side,
+        positions,
+        corner_uvs,
+        face_uv(uvs, side),
+        lighting::pack_light(MAX_LIGHT, 0),
+        [MAX_AO; 4],
+    );
+}
+
+// base부터 extent만큼 덮는 사각형 하나
+fn write_quad(
+    vertices: &mut Vec<u32>,
+    side: usize,
+    base: [f32; 3],
+    extent: [f32; 3],
+    region: AtlasRegion,
+    transform: UVTransform,
+    light: u8,
+    ao: [u8; 4],
+) {
+    let face = &FACES[side];
+    let mut positions = [[0.0; 3]; 4];
+    let mut uvs = [[0.0; 2]; 4];
+    for (corner, (position, uv)) in face.corners.iter().enumerate() {
+        for axis in 0..3 {
+            positions[corner][axis] = base[axis] + position[axis] * extent[axis];
+        }
+        uvs[corner] = transform.apply([uv[0] * extent[face.u_axis], uv[1] * extent[face.v_axis]]);
+    }
+
+    write_corners(vertices, side, positions, uvs, region, light, ao);
+}
+
+// 모서리 위치를 직접 정한 사각형 하나 (모서리 순서는 FaceShape::corners와 같다)
+fn write_corners(
+    vertices: &mut Vec<u32>,
+    side: usize,
+    positions: [[f32; 3]; 4],
+    uvs: [[f32; 2]; 4],
+    region: AtlasRegion,
+    light: u8,
+    ao: [u8; 4],
+) {
+    // 밝은 두 모서리를 잇는 대각선으로 나눠야 AO가 면 방향에 따라 다르게 보이지 않는다
+    let order = if ao[0] as u32 + ao[2] as u32 >= ao[1] as u32 + ao[3] as u32 {
+        [0, 1, 2, 3]
+    } else {
+        [1, 2, 3, 0]
+    };
+
+    for corner in order {
+        vertices.extend_from_slice(&pack_vertex(
+            positions[corner],
+            uvs[corner],
+            region.index,
+            side,
+            corner,
+            light,
+            ao[corner],
+        ));
+    }
+}
+
+// 각 방향, 각 층마다 보이는 면을 표시한 뒤 같은 블록, 같은 밝기, 같은 AO의 면을 직사각형으로 합친다
+pub fn build_greedy_mesh(chunk: &PaddedChunk, uv_map: &UVMap) -> MeshLayers {
+    let size = CHUNK_SIZE as i32;
+    let mask_index = |u: i32, v: i32| (v * size + u) as usize;
+
+    let mut layers = MeshLayers::default();
+    let mut mask: Vec<Option<(BlockID, u8, [u8; 4])>> = vec![None; (size * size) as usize];
+
+    for (side, face) in FACES.iter().enumerate() {
+        let normal_axis = 3 - face.u_axis - face.v_axis;
+
+        for slice in 0..size {
+            for v in 0..size {
+                for u in 0..size {
+                    let mut p = [0; 3];
+                    p[normal_axis] = slice;
+                    p[face.u_axis] = u;
+                    p[face.v_axis] = v;
+
+                    let block = chunk.get(p[0], p[1], p[2]);
+                    let visible = uv_map.contains_key(&block)
+                        && !block.is_fluid()
+                        && !has_custom_shape(block)
+                        && chunk
+                            .get(
+                                p[0] + face.normal[0],
+                                p[1] + face.normal[1],
+                                p[2] + face.normal[2],
+                            )
+                            .is_transparent();
+
+                    mask[mask_index(u, v)] = if visible {
+                        Some((
+                            block,
+                            chunk.face_light(p[0], p[1], p[2], face),
+                            chunk.face_ao(p[0], p[1], p[2], face),
+                        ))
+                    } else {
+                        None
+                    };
+                }
+            }
+
+            for v in 0..size {
+                let mut u = 0;
+                while u < size {
+                    let cell = match mask[mask_index(u, v)] {
+                        Some(cell) => cell,
+                        None => {
+                            u += 1;
+                            continue;
+                        }
+                    };
+
+                    let mut width = 1;
+                    while u + width < size && mask[mask_index(u + width, v)] == Some(cell) {
+                        width += 1;
+                    }
+
+                    let mut height = 1;
+                    'grow: while v + height < size {
+                        for du in 0..width {
+                            if mask[mask_index(u + du, v + height)] != Some(cell) {
+                                break 'grow;
+                            }
+                        }
+                        height += 1;
+                    }
+
+                    for dv in 0..height {
+                        for du in 0..width {
+                            mask[mask_index(u + du, v + dv)] = None;
+                        }
+                    }
+
+                    let mut base = [0.0; 3];
+                    base[normal_axis] = slice as f32;
+                    base[face.u_axis] = u as f32;
+                    base[face.v_axis] = v as f32;
+
+                    let mut extent = [1.0; 3];
+                    extent[face.u_axis] = width as f32;
+                    extent[face.v_axis] = height as f32;
+
+                    let (block, light, ao) = cell;
+                    let uvs = get_uv_every_side(*uv_map.get(&block).unwrap());
+                    let (texture_side, transform) = face_texture(block, side);
+                    write_quad(
+                        &mut layers[block.definition().render_layer() as usize],
+                        side,
+                        base,
+                        extent,
+                        face_uv(uvs, texture_side),
+                        transform,
+                        light,
+                        ao,
+                    );
+
+                    u += width;
+                }
+            }
+        }
+    }
+
+    build_shaped_mesh(chunk, uv_map, &mut layers);
+    build_fluid_mesh(chunk, uv_map, &mut layers);
+    layers
+}
+
+// 모양이 있는 블록의 상자마다 여섯 면을 그린다 - 텍스쳐는 상자가 덮는 만큼만 잘라 쓴다
+fn build_shaped_mesh(chunk: &PaddedChunk, uv_map: &UVMap, layers: &mut MeshLayers) {
+    let size = CHUNK_SIZE as i32;
+
+    for y in 0..size {
+        for z in 0..size {
+            for x in 0..size {
+                let block = chunk.get(x, y, z);
+                if !has_custom_shape(block) {
+                    continue;
+                }
+                let uvs = match uv_map.get(&block) {
+                    Some(&uvs) => get_uv_every_side(uvs),
+                    None => continue,
+                };
+                let vertices = &mut layers[block.definition().render_layer() as usize];
+
+                for aabb in &block.definition().shape().boxes {
+                    for (side, face) in FACES.iter().enumerate() {
+                        let axis = 3 - face.u_axis - face.v_axis;
+                        let on_border = if face.normal[axis] > 0 {
+                            aabb.maxs[axis] >= 1.0
+                        } else {
+                            aabb.mins[axis] <= 0.0
+                        };
+                        let neighbor =
+                            chunk.get(x + face.normal[0], y + face.normal[1], z + face.normal[2]);
+                        if on_border && !neighbor.is_transparent() {
+                            continue;
+                        }
+
+                        let (texture_side, transform) = face_texture(block, side);
+                        let mut positions = [[0.0; 3]; 4];
+                        let mut corner_uvs = [[0.0; 2]; 4];
+                        for (corner, (position, _)) in face.corners.iter().enumerate() {
+                            let local = [0, 1, 2].map(|i| {
+                                if position[i] > 0.5 {
+                                    aabb.maxs[i]
+                                } else {
+                                    aabb.mins[i]
+                                }
+                            });
+                            positions[corner] = [
+                                x as f32 + local[0],
+                                y as f32 + local[1],
+                                z as f32 + local[2],
+                            ];
+                            corner_uvs[corner] = transform.apply(uv_on_face(face, local));
+                        }
+
+                        let light = if on_border {
+                            chunk.face_light(x, y, z, face)
+                        } else {
+                            chunk.get_light(x, y, z)
+                        };
+                        write_corners(
+                            vertices,
+                            side,
+                            positions,
+                            corner_uvs,
+                            face_uv(uvs, texture_side),
+                            light,
+                            [MAX_AO; 4],
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn is_same_fluid(block: BlockID, source: BlockID) -> bool {
+    block
+        .fluid_state()
+        .is_some_and(|state| state.source == source)
+}
+
+// 모서리를 공유하는 유체 블록 4개의 표면 높이 평균
+fn fluid_corner_height(chunk: &PaddedChunk, x: i32, y: i32, z: i32, source: BlockID) -> f32 {
+    let mut total = 0.0;
+    let mut count = 0;
+
+    for bz in z - 1..=z {
+        for bx in x - 1..=x {
+            if is_same_fluid(chunk.get(bx, y + 1, bz), source) {
+                return 1.0;
+            }
+
+            let block = chunk.get(bx, y, bz);
+            if is_same_fluid(block, source) {
+                total += fluids::fluid_height(block).unwrap();
+                count += 1;
+            }
+        }
+    }
+
+    total / count as f32
+}
+
+// 유체 블록은 표면 높이가 수위에 따라 달라서 합치지 않고 블록마다 면을 만든다
+fn build_fluid_mesh(chunk: &PaddedChunk, uv_map: &UVMap, layers: &mut MeshLayers) {
+    let size = CHUNK_SIZE as i32;
+
+    for y in 0..size {
+        for z in 0..size {
+            for x in 0..size {
+                let block = chunk.get(x, y, z);
+                let source = match block.fluid_state() {
+                    Some(state) => state.source,
+                    None => continue,
+                };
+                let uvs = match uv_map.get(&block) {
+                    Some(&uvs) => get_uv_every_side(uvs),
+                    None => continue,
+                };
+                let vertices = &mut layers[block.definition().render_layer() as usize];
+
+                // (x, z) 모서리마다 표면 높이
+                let mut heights = [[0.0; 2]; 2];
+                for (cz, row) in heights.iter_mut().enumerate() {
+                    for (cx, height) in row.iter_mut().enumerate() {
+                        *height =
+                            fluid_corner_height(chunk, x + cx as i32, y, z + cz as i32, source);
+                    }
+                }
+
+                for (side, face) in FACES.iter().enumerate() {
+                    let neighbor =
+                        chunk.get(x + face.normal[0], y + face.normal[1], z + face.normal[2]);
+                    if is_same_fluid(neighbor, source) {
+                        continue;
+                    }
+                    // 수면은 위에 불투명한 블록이 있어도 낮게 깔릴 수 있어서 항상 그린다
+                    if face.normal[1] != 1 && !neighbor.is_transparent() {
+                        continue;
+                    }
+
+                    let mut positions = [[0.0; 3]; 4];
+                    let mut corner_uvs = [[0.0; 2]; 4];
+                    for (corner, (position, uv)) in face.corners.iter().enumerate() {
+                        let mut top = position[1];
+                        if top > 0.5 {
+                            top = heights[position[2] as usize][position[0] as usize];
+                        }
+
+                        positions[corner] = [
+                            x as f32 + position[0],
+                            y as f32 + top,
+                            z as f32 + position[2],
+                        ];
+                        corner_uvs[corner] = if face.v_axis == 1 { [uv[0], top] } else { *uv };
+                    }
+
+                    write_corners(
+                        vertices,
+                        side,
+                        positions,
+                        corner_uvs,
+                        face_uv(uvs, side),
+                        chunk.face_light(x, y, z, face),
+                        [MAX_AO; 4],
+                    );
+                }
+            }
+        }
+    }
+}
+
+// 반투명 면을 카메라(메시 안 좌표)에서 먼 것부터 정렬한다
+pub fn sort_back_to_front(vertices: &mut Vec<u32>, camera: [f32; 3]) {
+    let quad_len = WORDS_PER_VERTEX * VERTICES_PER_QUAD;
+    let distance = |quad: &[u32]| {
+        let mut center = [0.0; 3];
+        for vertex in quad.chunks(WORDS_PER_VERTEX) {
+            let position = unpack_position(vertex);
+            for axis in 0..3 {
+                center[axis] += position[axis] / VERTICES_PER_QUAD as f32;
+            }
+        }
+        (0..3)
+            .map(|axis| (center[axis] - camera[axis]).powi(2))
+            .sum::<f32>()
+    };
+
+    let mut quads: Vec<(f32, &[u32])> = vertices
+        .chunks(quad_len)
+        .map(|quad| (distance(quad), quad))
+        .collect();
+    quads.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    *vertices = quads
+        .into_iter()
+        .flat_map(|(_, quad)| quad.iter().copied())
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_registry::BLOCKS;
+    use crate::block_texture_sides::BlockFaces;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    use std::collections::HashMap;
+
+    // 블록마다, 옆면/윗면/아랫면마다 다른 타일 번호
+    fn test_uv_map(blocks: &[BlockID]) -> UVMap {
+        let region = |index: u32| AtlasRegion {
+            index,
+            x: 0,
+            y: 0,
+            width: 16,
+            height: 16,
+            uv: (0.0, 0.0, 1.0, 1.0),
+        };
+
+        blocks
+            .iter()
+            .map(|&block| {
+                let index = block.0 as u32 * 3;
+                let faces = BlockFaces::Sides {
+                    sides: region(index),
+                    top: region(index + 1),
+                    bottom: region(index + 2),
+                };
+                (block, faces)
+            })
+            .collect()
+    }
+
+    // (렌더 단계, 면, 타일)마다 사각형들이 덮는 넓이
+    fn covered_area(layers: &MeshLayers) -> HashMap<(usize, u32, u32), f32> {
+        let mut areas = HashMap::new();
+        for (layer, vertices) in layers.iter().enumerate() {
+            for quad in vertices.chunks(WORDS_PER_VERTEX * VERTICES_PER_QUAD) {
+                let corners: Vec<[f32; 3]> =
+                    quad.chunks(WORDS_PER_VERTEX).map(unpack_position).collect();
+                let edge = |to: usize| [0, 1, 2].map(|axis| corners[to][axis] - corners[0][axis]);
+                let (a, b) = (edge(1), edge(3));
+                let cross = [
+                    a[1] * b[2] - a[2] * b[1],
+                    a[2] * b[0] - a[0] * b[2],
+                    a[0] * b[1] - a[1] * b[0],
+                ];
+                let area = cross.iter().map(|c| c * c).sum::<f32>().sqrt();
+
+                let side = (quad[0] >> 15) & 0x7;
+                let tile = quad[1] & 0xFFF;
+                *areas.entry((layer, side, tile)).or_insert(0.0) += area;
+            }
+        }
+        areas
+    }
+
+    #[test]
+    fn greedy_mesh_covers_the_same_faces_as_per_face_mesh() {
+        // 두 방식이 함께 쓰는 모양 있는 블록과 유체는 뺀다
+        let blocks: Vec<BlockID> = (1..BLOCKS.len())
+            .map(|id| BlockID(id as u16))
+            .filter(|block| !block.is_fluid() && !has_custom_shape(*block))
+            .collect();
+        assert!(blocks.len() >= 3);
+        let uv_map = test_uv_map(&blocks);
+
+        // 같은 블록끼리 붙은 면이 많도록 블록 몇 종류와 공기만 쓰고, 빛도 섞는다
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        let mut chunk = PaddedChunk::new();
+        for y in -1..=CHUNK_SIZE as i32 {
+            for z in -1..=CHUNK_SIZE as i32 {
+                for x in -1..=CHUNK_SIZE as i32 {
+                    let block = match rng.gen_range(0..6) {
+                        0..=2 => BlockID::AIR,
+                        n => blocks[n - 3],
+                    };
+                    chunk.set(x, y, z, block);
+                    chunk.set_light(x, y, z, lighting::pack_light(rng.gen_range(14..=15), 0));
+                }
+            }
+        }
+
+        let greedy = build_mesh(&chunk, &uv_map, MeshingStrategy::Greedy);
+        let per_face = build_mesh(&chunk, &uv_map, MeshingStrategy::PerFace);
+        let quads = |layers: &MeshLayers| layers.iter().map(Vec::len).sum::<usize>();
+        assert!(quads(&greedy) < quads(&per_face));
+
+        let (greedy, per_face) = (covered_area(&greedy), covered_area(&per_face));
+        assert!(!per_face.is_empty());
+        let mut keys: Vec<_> = per_face.keys().chain(greedy.keys()).copied().collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            assert_eq!(
+                greedy.get(&key),
+                per_face.get(&key),
+                "(layer, side, tile) {key:?}"
+            );
+        }
+    }
+}
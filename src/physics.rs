@@ -1,4 +1,5 @@
 use crate::aabb::AABB;
+use crate::voxel_shape::VoxelShape;
 use crate::{PLAYER_EYES_HEIGHT, PLAYER_HALF_WIDTH, PLAYER_HEIGHT};
 use nalgebra_glm::{vec3, Vec3};
 use std::ops::{Add, Mul};
@@ -72,6 +73,8 @@ impl Mul<f32> for PlayerPhysicsState {
 pub struct PhysicsManager {
     pub t: f32,
     pub dt: f32,
+    // 지금까지 진행한 고정 틱 수
+    pub ticks: u64,
     pub current_time: time::Instant,
     pub accumulator: f32,
     pub previous_state: PlayerPhysicsState,
@@ -83,6 +86,7 @@ impl PhysicsManager {
         Self {
             t: 0.0,
             dt,
+            ticks: 0,
             current_time: time::Instant::now(),
             accumulator: 0.0,
             previous_state: initial_state.clone(),
@@ -108,6 +112,7 @@ impl PhysicsManager {
             self.previous_state = self.current_state.clone();
             self.current_state = integrate(self.previous_state.clone(), self.t, self.dt);
             self.t += self.dt;
+            self.ticks += 1;
             self.accumulator -= self.dt;
         }
 
@@ -124,6 +129,62 @@ impl PhysicsManager {
     }
 }
 
-pub fn get_block_aabb(mins: &Vec3) -> AABB {
-    AABB::new(mins.clone(), mins + vec3(1.0, 1.0, 1.0))
+// 축마다 따로 움직여 블록의 충돌 모양에 붙인다 - 아래로 가다 멈추면(땅에 서 있으면) true
+pub fn move_and_collide(
+    aabb: &mut AABB,
+    velocity: &mut Vec3,
+    dt: f32,
+    collision_shape: &dyn Fn(i32, i32, i32) -> Option<&'static VoxelShape>,
+) -> bool {
+    let mut on_ground = false;
+
+    for axis in 0..3 {
+        let mut step = vec3(0.0, 0.0, 0.0);
+        step[axis] = velocity[axis] * dt;
+        if step[axis] == 0.0 {
+            continue;
+        }
+        aabb.translate(&step);
+
+        // 겹친 상자 중 가장 멀리 밀어내야 하는 상자의 면
+        let mut contact: Option<f32> = None;
+        for y in aabb.mins.y.floor() as i32 - 1..=aabb.maxs.y.floor() as i32 {
+            for z in aabb.mins.z.floor() as i32..=aabb.maxs.z.floor() as i32 {
+                for x in aabb.mins.x.floor() as i32..=aabb.maxs.x.floor() as i32 {
+                    let shape = match collision_shape(x, y, z) {
+                        Some(shape) => shape,
+                        None => continue,
+                    };
+
+                    for block_aabb in shape.at(x, y, z) {
+                        if !aabb.intersects(&block_aabb) {
+                            continue;
+                        }
+
+                        contact = Some(match (contact, step[axis] < 0.0) {
+                            (None, true) => block_aabb.maxs[axis],
+                            (None, false) => block_aabb.mins[axis],
+                            (Some(face), true) => face.max(block_aabb.maxs[axis]),
+                            (Some(face), false) => face.min(block_aabb.mins[axis]),
+                        });
+                    }
+                }
+            }
+        }
+
+        if let Some(face) = contact {
+            let mut shift = vec3(0.0, 0.0, 0.0);
+            shift[axis] = if step[axis] < 0.0 {
+                face - aabb.mins[axis]
+            } else {
+                face - aabb.maxs[axis]
+            };
+            aabb.translate(&shift);
+
+            on_ground |= axis == 1 && step[axis] < 0.0;
+            velocity[axis] = 0.0;
+        }
+    }
+
+    on_ground
 }
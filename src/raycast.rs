@@ -1,14 +1,16 @@
 // Voxel을 위한 특수한 Raycast(정수 기준 raycast)
+use crate::voxel_shape::VoxelShape;
 use nalgebra_glm::{floor, IVec3, Vec3};
 use num_traits::float::FloatCore;
 
 // direction must be normalized
-pub fn raycast<T>(
-    get_voxel: &dyn Fn(i32, i32, i32) -> Option<T>,
+// 맞은 voxel, 맞은 면의 normal, 맞은 점 - 선택 모양의 상자에 닿아야 맞은 것으로 친다
+pub fn raycast<'a, T>(
+    get_voxel: &dyn Fn(i32, i32, i32) -> Option<(T, &'a VoxelShape)>,
     origin: &Vec3,
     direction: &Vec3,
     distance: f32,
-) -> Option<(T, IVec3)> {
+) -> Option<(T, IVec3, Vec3)> {
     let mut t = 0.0f32;
     // 카메라 위치를 정수로 가져옴
     let mut i = floor(&origin).map(|x| x as i32);
@@ -32,26 +34,24 @@ pub fn raycast<T>(
         }
     });
 
-    let mut hit_pos = Vec3::new(0.0, 0.0, 0.0);
     let mut hit_norm = IVec3::new(0, 0, 0);
 
     let mut stepped_index = -1;
     while t <= distance {
         // exit check
-        if let Some(voxel) = get_voxel(i.x, i.y, i.z) {
-            // origin에 있는 블록을 가져옴
-            hit_pos = origin.zip_map(&direction, |p, d| p + t * d);
-            // 충돌이 발생함 - 충돌한 면의 Normal Vector를 반환
-            if stepped_index == 0 {
-                hit_norm[0] = -step.x;
+        if let Some((voxel, shape)) = get_voxel(i.x, i.y, i.z) {
+            // 블록 안의 모양에 실제로 닿았을 때만 충돌로 친다
+            if let Some((hit_t, axis)) = shape.ray_hit(i.x, i.y, i.z, origin, direction) {
+                if hit_t <= distance {
+                    let hit_pos = origin.zip_map(&direction, |p, d| p + hit_t * d);
+                    // 충돌이 발생함 - 충돌한 면의 Normal Vector를 반환
+                    // (카메라가 이미 모양 안에 있으면 마지막으로 넘어온 면을 쓴다)
+                    if let Some(axis) = axis.or_else(|| usize::try_from(stepped_index).ok()) {
+                        hit_norm[axis] = -step[axis];
+                    }
+                    return Some((voxel, hit_norm, hit_pos));
+                }
             }
-            if stepped_index == 1 {
-                hit_norm[1] = -step.y;
-            }
-            if stepped_index == 2 {
-                hit_norm[2] = -step.z;
-            }
-            return Some((voxel, hit_norm));
         }
 
         // advance t to next nearest voxel boundary(가장 가까운 정수 위치가 잡히도록 t를 조정해준다.)
@@ -85,7 +85,5 @@ pub fn raycast<T>(
     }
 
     // no voxel hit found - return None
-    hit_pos = origin.zip_map(&direction, |p, d| p + t * d);
-
     None
 }
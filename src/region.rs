@@ -0,0 +1,487 @@
+use crate::block_registry::BLOCKS;
+use crate::block_storage::{BlockStorage, PalettedBlocks};
+use crate::block_ticks::PendingTick;
+use crate::chunk::{BlockID, Chunk};
+use crate::chunk_manager::{CHUNK_SIZE, CHUNK_VOLUME};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// 리전 하나에 들어가는 청크 수 (축마다)
+pub const REGION_SIZE: i32 = 8;
+pub const REGION_VOLUME: usize = (REGION_SIZE * REGION_SIZE * REGION_SIZE) as usize;
+
+const REGION_MAGIC: &[u8; 4] = b"MCRG";
+const REGION_VERSION: u32 = 3;
+
+pub type ChunkCoords = (i32, i32, i32);
+pub type RegionCoords = (i32, i32, i32);
+
+pub fn chunk_to_region_coords((x, y, z): ChunkCoords) -> (RegionCoords, usize) {
+    let region = (
+        x.div_euclid(REGION_SIZE),
+        y.div_euclid(REGION_SIZE),
+        z.div_euclid(REGION_SIZE),
+    );
+    let (lx, ly, lz) = (
+        x.rem_euclid(REGION_SIZE),
+        y.rem_euclid(REGION_SIZE),
+        z.rem_euclid(REGION_SIZE),
+    );
+
+    (
+        region,
+        ((ly * REGION_SIZE + lz) * REGION_SIZE + lx) as usize,
+    )
+}
+
+fn region_to_chunk_coords((rx, ry, rz): RegionCoords, index: usize) -> ChunkCoords {
+    let index = index as i32;
+    let lx = index % REGION_SIZE;
+    let lz = (index / REGION_SIZE) % REGION_SIZE;
+    let ly = index / (REGION_SIZE * REGION_SIZE);
+
+    (
+        rx * REGION_SIZE + lx,
+        ry * REGION_SIZE + ly,
+        rz * REGION_SIZE + lz,
+    )
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+fn read_u8(reader: &mut &[u8]) -> io::Result<u8> {
+    let mut bytes = [0u8; 1];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+fn read_u16(reader: &mut &[u8]) -> io::Result<u16> {
+    let mut bytes = [0u8; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32(reader: &mut &[u8]) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut &[u8]) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+// 저장된 청크 하나 - 블록과 아직 처리되지 않은 예약 업데이트
+pub struct SavedChunk {
+    pub blocks: BlockStorage,
+    pub scheduled_ticks: Vec<PendingTick>,
+}
+
+// 리전 파일 하나의 내용 - 블록 이름 팔레트의 번호로 저장해서 레지스트리 순서가 바뀌어도 읽힌다
+#[derive(Default)]
+pub struct Region {
+    pub chunks: HashMap<usize, SavedChunk>,
+}
+
+impl Region {
+    pub fn encode(&self) -> io::Result<Vec<u8>> {
+        let mut palette: Vec<BlockID> = Vec::new();
+        let mut palette_index: HashMap<BlockID, u16> = HashMap::new();
+        let mut region_index = |block: BlockID| {
+            *palette_index.entry(block).or_insert_with(|| {
+                palette.push(block);
+                (palette.len() - 1) as u16
+            })
+        };
+
+        let mut body = Vec::new();
+        let mut indices: Vec<&usize> = self.chunks.keys().collect();
+        indices.sort();
+
+        body.extend_from_slice(&(indices.len() as u16).to_le_bytes());
+        for &index in indices {
+            body.extend_from_slice(&(index as u16).to_le_bytes());
+
+            let chunk = &self.chunks[&index];
+            match &chunk.blocks {
+                BlockStorage::Single(block) => {
+                    body.push(0);
+                    body.extend_from_slice(&region_index(*block).to_le_bytes());
+                }
+                BlockStorage::Paletted(blocks) => {
+                    body.push(1);
+                    body.extend_from_slice(&(blocks.palette().len() as u16).to_le_bytes());
+                    for &block in blocks.palette() {
+                        body.extend_from_slice(&region_index(block).to_le_bytes());
+                    }
+                    body.push(blocks.bits() as u8);
+                    for word in blocks.data() {
+                        body.extend_from_slice(&word.to_le_bytes());
+                    }
+                }
+            }
+
+            body.extend_from_slice(&(chunk.scheduled_ticks.len() as u16).to_le_bytes());
+            for tick in &chunk.scheduled_ticks {
+                let index = (tick.y * CHUNK_SIZE + tick.z) * CHUNK_SIZE + tick.x;
+                body.extend_from_slice(&(index as u16).to_le_bytes());
+                body.extend_from_slice(&tick.delay.to_le_bytes());
+            }
+        }
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&(palette.len() as u16).to_le_bytes())?;
+        for block in palette {
+            let name = block.definition().name.as_bytes();
+            encoder.write_all(&(name.len() as u16).to_le_bytes())?;
+            encoder.write_all(name)?;
+        }
+        encoder.write_all(&body)?;
+
+        let mut data = Vec::new();
+        data.extend_from_slice(REGION_MAGIC);
+        data.extend_from_slice(&REGION_VERSION.to_le_bytes());
+        data.extend_from_slice(&encoder.finish()?);
+
+        Ok(data)
+    }
+
+    pub fn decode(data: &[u8]) -> io::Result<Region> {
+        if data.len() < 8 || &data[0..4] != REGION_MAGIC {
+            return Err(invalid_data("Not a region file"));
+        }
+        let version = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+        if version == 0 || version > REGION_VERSION {
+            return Err(invalid_data("Unsupported region file version"));
+        }
+
+        let mut payload = Vec::new();
+        ZlibDecoder::new(&data[8..]).read_to_end(&mut payload)?;
+        let mut reader = payload.as_slice();
+
+        let palette_len = read_u16(&mut reader)?;
+        let mut palette = Vec::with_capacity(palette_len as usize);
+        for _ in 0..palette_len {
+            let len = read_u16(&mut reader)? as usize;
+            if reader.len() < len {
+                return Err(invalid_data("Truncated block palette"));
+            }
+            let name = std::str::from_utf8(&reader[..len])
+                .map_err(|_| invalid_data("Block name is not UTF-8"))?;
+            reader = &reader[len..];
+
+            palette.push(BLOCKS.get_id(name).unwrap_or_else(|| {
+                eprintln!("Unknown block '{name}' in region file, replacing it with air");
+                BlockID::AIR
+            }));
+        }
+
+        let read_block = |reader: &mut &[u8]| -> io::Result<BlockID> {
+            let i = read_u16(reader)? as usize;
+            palette
+                .get(i)
+                .copied()
+                .ok_or_else(|| invalid_data("Palette index out of range"))
+        };
+
+        let mut region = Region::default();
+        let chunk_count = read_u16(&mut reader)?;
+        for _ in 0..chunk_count {
+            let index = read_u16(&mut reader)? as usize;
+            if index >= REGION_VOLUME {
+                return Err(invalid_data("Chunk index out of range"));
+            }
+
+            let storage = if version == 1 {
+                // 버전 1: 블록마다 팔레트 번호 하나
+                let mut blocks = Vec::with_capacity(CHUNK_VOLUME as usize);
+                for _ in 0..CHUNK_VOLUME {
+                    blocks.push(read_block(&mut reader)?);
+                }
+                BlockStorage::from_blocks(&blocks)
+            } else {
+                match read_u8(&mut reader)? {
+                    0 => BlockStorage::Single(read_block(&mut reader)?),
+                    1 => {
+                        let len = read_u16(&mut reader)?;
+                        let mut chunk_palette = Vec::with_capacity(len as usize);
+                        for _ in 0..len {
+                            chunk_palette.push(read_block(&mut reader)?);
+                        }
+
+                        let bits = read_u8(&mut reader)? as u32;
+                        let per_word = 64 / bits.clamp(1, 16) as usize;
+                        let words = (CHUNK_VOLUME as usize).div_ceil(per_word);
+                        let mut data = Vec::with_capacity(words);
+                        for _ in 0..words {
+                            data.push(read_u64(&mut reader)?);
+                        }
+
+                        let mut blocks = PalettedBlocks::from_raw(chunk_palette, bits, data)
+                            .map(BlockStorage::Paletted)
+                            .ok_or_else(|| invalid_data("Malformed chunk palette"))?;
+                        blocks.optimize();
+                        blocks
+                    }
+                    _ => return Err(invalid_data("Unknown chunk storage kind")),
+                }
+            };
+
+            // 버전 3부터 예약 업데이트가 함께 저장된다
+            let mut scheduled_ticks = Vec::new();
+            if version >= 3 {
+                for _ in 0..read_u16(&mut reader)? {
+                    let index = read_u16(&mut reader)? as u32;
+                    if index >= CHUNK_VOLUME {
+                        return Err(invalid_data("Scheduled tick out of range"));
+                    }
+                    scheduled_ticks.push(PendingTick {
+                        x: index % CHUNK_SIZE,
+                        y: index / (CHUNK_SIZE * CHUNK_SIZE),
+                        z: (index / CHUNK_SIZE) % CHUNK_SIZE,
+                        delay: read_u32(&mut reader)?,
+                    });
+                }
+            }
+
+            region.chunks.insert(
+                index,
+                SavedChunk {
+                    blocks: storage,
+                    scheduled_ticks,
+                },
+            );
+        }
+
+        Ok(region)
+    }
+}
+
+#[derive(Clone)]
+pub struct RegionStorage {
+    directory: PathBuf,
+    // 리전 파일을 읽고 다시 쓰는 동안 다른 워커의 저장이 끼어들지 않게 한다
+    writing: Arc<Mutex<()>>,
+}
+
+impl RegionStorage {
+    pub fn new(directory: impl AsRef<Path>) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+            writing: Arc::new(Mutex::new(())),
+        }
+    }
+
+    fn seed_path(&self) -> PathBuf {
+        self.directory.join("seed")
+    }
+
+    // 월드를 처음 만들 때 정한 시드 (아직 없으면 None)
+    pub fn load_seed(&self) -> io::Result<Option<u64>> {
+        match fs::read_to_string(self.seed_path()) {
+            Ok(text) => text
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|_| invalid_data("Malformed world seed")),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn save_seed(&self, seed: u64) -> io::Result<()> {
+        fs::create_dir_all(&self.directory)?;
+        fs::write(self.seed_path(), seed.to_string())
+    }
+
+    fn region_path(&self, (rx, ry, rz): RegionCoords) -> PathBuf {
+        self.directory.join(format!("r.{rx}.{ry}.{rz}.region"))
+    }
+
+    pub fn load_region(&self, coords: RegionCoords) -> io::Result<Option<Region>> {
+        match fs::read(self.region_path(coords)) {
+            Ok(data) => Region::decode(&data).map(Some),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    // 리전 파일 하나를 통째로 다시 쓴다 (임시 파일에 쓴 뒤 교체)
+    pub fn save_region(&self, coords: RegionCoords, region: &Region) -> io::Result<()> {
+        fs::create_dir_all(&self.directory)?;
+
+        let path = self.region_path(coords);
+        let temp_path = path.with_extension("region.tmp");
+        fs::write(&temp_path, region.encode()?)?;
+        fs::rename(temp_path, path)
+    }
+
+    // coords 중 저장된 청크를 모두 불러온다 (리전 파일마다 한 번씩 읽는다)
+    pub fn load_chunks(
+        &self,
+        coords: impl Iterator<Item = ChunkCoords>,
+    ) -> io::Result<HashMap<ChunkCoords, SavedChunk>> {
+        let mut by_region: HashMap<RegionCoords, Vec<usize>> = HashMap::new();
+        for chunk_coords in coords {
+            let (region, index) = chunk_to_region_coords(chunk_coords);
+            by_region.entry(region).or_default().push(index);
+        }
+
+        let mut chunks = HashMap::new();
+        for (region_coords, indices) in by_region {
+            if let Some(mut region) = self.load_region(region_coords)? {
+                for index in indices {
+                    if let Some(chunk) = region.chunks.remove(&index) {
+                        chunks.insert(region_to_chunk_coords(region_coords, index), chunk);
+                    }
+                }
+            }
+        }
+
+        Ok(chunks)
+    }
+
+    // 변경된 청크들을 리전별로 묶어서 저장
+    pub fn save_chunks<'a>(
+        &self,
+        chunks: impl Iterator<Item = (ChunkCoords, &'a Chunk)>,
+    ) -> io::Result<()> {
+        let _writing = self.writing.lock().unwrap();
+
+        let mut by_region: HashMap<RegionCoords, Vec<(usize, &Chunk)>> = HashMap::new();
+        for (chunk_coords, chunk) in chunks {
+            let (region, index) = chunk_to_region_coords(chunk_coords);
+            by_region.entry(region).or_default().push((index, chunk));
+        }
+
+        for (region_coords, chunks) in by_region {
+            let mut region = self.load_region(region_coords)?.unwrap_or_default();
+            for (index, chunk) in chunks {
+                region.chunks.insert(
+                    index,
+                    SavedChunk {
+                        blocks: chunk.blocks().clone(),
+                        scheduled_ticks: chunk.scheduled_ticks.clone(),
+                    },
+                );
+            }
+            self.save_region(region_coords, &region)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_manager::ChunkManager;
+    use std::time::{Duration, Instant};
+
+    fn loaded_manager(directory: &Path, seed: u64) -> ChunkManager {
+        let mut manager = ChunkManager::with_storage(RegionStorage::new(directory), seed);
+        manager.render_distance = 1;
+
+        // 워커가 (0, 0)과 (-1, 0) 기둥을 디스크에서 불러올 때까지 기다린다
+        let start = Instant::now();
+        while !(manager.is_column_loaded(0, 0) && manager.is_column_loaded(-1, 0)) {
+            assert!(
+                start.elapsed() < Duration::from_secs(30),
+                "Columns never loaded"
+            );
+            manager.update_loaded_chunks((0, 0, 0));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        manager
+    }
+
+    #[test]
+    fn saved_chunks_load_back_unchanged() {
+        assert!(
+            BLOCKS.len() > 17,
+            "The test needs a palette with more than 16 entries"
+        );
+        let directory =
+            std::env::temp_dir().join(format!("region-round-trip-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        // 블록 종류가 16개보다 많은 청크, 공기뿐인 청크, 음수 좌표의 청크
+        let mut paletted = Chunk::empty();
+        let mut sparse = Chunk::empty();
+        for y in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    let index = (x * 7 + y * 13 + z * 3) as usize;
+                    paletted.set_block(x, y, z, BlockID((index % BLOCKS.len()) as u16));
+                    if (x + y + z) % 5 == 0 {
+                        sparse.set_block(x, y, z, BlockID((1 + index % 3) as u16));
+                    }
+                }
+            }
+        }
+        let chunks = vec![
+            ((0, 0, 0), paletted),
+            ((0, 1, 0), Chunk::empty()),
+            ((-1, 2, 0), sparse),
+        ];
+
+        let mut manager = ChunkManager::with_storage(RegionStorage::new(&directory), 1);
+        for (coords, chunk) in &chunks {
+            let mut saved = Chunk::from_storage(chunk.blocks().clone());
+            saved.modified = true;
+            manager.loaded_chunks.insert(*coords, saved);
+        }
+        manager.save().unwrap();
+        drop(manager);
+
+        // 저장된 청크는 지형 생성으로 덮어쓰이지 않아야 한다
+        let reloaded = loaded_manager(&directory, 1);
+        for (coords, chunk) in &chunks {
+            let loaded = &reloaded.loaded_chunks[coords];
+            for y in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    for x in 0..CHUNK_SIZE {
+                        assert_eq!(
+                            loaded.get_block(x, y, z),
+                            chunk.get_block(x, y, z),
+                            "Block ({x}, {y}, {z}) of chunk {coords:?}"
+                        );
+                    }
+                }
+            }
+        }
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn unloaded_columns_are_saved_in_the_background() {
+        let directory = std::env::temp_dir().join(format!("region-unload-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let obsidian = BLOCKS.id("obsidian");
+
+        let mut manager = loaded_manager(&directory, 1);
+        manager.set_block(3, 200, 5, obsidian);
+
+        // 멀리 떠나면 (0, 0) 기둥이 내려지고 워커가 저장한다
+        manager.update_loaded_chunks((1000, 0, 0));
+        assert!(!manager.is_column_loaded(0, 0));
+        manager.save().unwrap();
+        drop(manager);
+
+        let reloaded = loaded_manager(&directory, 1);
+        assert_eq!(reloaded.get_block(3, 200, 5), Some(obsidian));
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
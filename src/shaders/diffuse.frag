@@ -1,20 +1,42 @@
-#version 460 core
-
-out vec4 Color;
-
-uniform sampler2D tex;
-
-in VertexAttributes{
-    vec3 frag_pos;
-    vec2 texture_coords;
-} attrs;
-
-void main(){
-    vec4 diffuse_frag = texture(tex, attrs.texture_coords);
-
-    if (diffuse_frag.a == 0){
-        discard;
-    }
-
-    Color = diffuse_frag;
+#version 460 core
+
+out vec4 Color;
+
+uniform sampler2D tex;
+
+// 빛이 전혀 없는 곳에서도 완전히 검게 보이지는 않게 한다
+const float MIN_BRIGHTNESS = 0.05;
+// 세 블록에 모두 가려진 모서리의 밝기
+const float MIN_AO = 0.4;
+
+in VertexAttributes{
+    vec3 frag_pos;
+    vec2 texture_coords;
+    vec4 texture_rect;
+    vec2 light;
+    float ao;
+} attrs;
+
+void main(){
+    // 아틀라스 안의 텍스쳐 영역에서 반복
+    vec2 rect_size = attrs.texture_rect.zw - attrs.texture_rect.xy;
+    vec2 uv = mix(attrs.texture_rect.xy, attrs.texture_rect.zw, fract(attrs.texture_coords));
+    // fract가 블록 경계마다 uv를 되돌리므로 mipmap 단계는 되돌리기 전의 좌표로 정한다
+    vec4 diffuse_frag = textureGrad(
+        tex,
+        uv,
+        dFdx(attrs.texture_coords) * rect_size,
+        dFdy(attrs.texture_coords) * rect_size
+    );
+
+    if (diffuse_frag.a == 0){
+        discard;
+    }
+
+    // 하늘빛과 블록 빛 중 밝은 쪽, 빛 단계마다 밝기가 곡선으로 줄어든다
+    float level = max(attrs.light.x, attrs.light.y);
+    float brightness = max(level / (4.0 - 3.0 * level), MIN_BRIGHTNESS);
+    brightness *= mix(MIN_AO, 1.0, attrs.ao);
+
+    Color = vec4(diffuse_frag.rgb * brightness, diffuse_frag.a);
 }
\ No newline at end of file
@@ -1,21 +1,52 @@
-#version 460 core
-
-uniform mat4 model;
-uniform mat4 view;
-uniform mat4 projection;
-
-layout (location = 0) in vec3 pos;
-layout (location = 1) in vec2 texture_coords;
-
-out VertexAttributes{
-    vec3 frag_pos;
-    vec2 texture_coords;
-} attrs;
-
-void main(){
-    gl_Position = projection * view * model * vec4(pos, 1.0f);
-
-    // frag 쉐이더는 위치만 필요하므로 vec3으로 정해줌
-    attrs.frag_pos = vec3(view * model * vec4(pos, 1.0f));
-    attrs.texture_coords = texture_coords;
-}
\ No newline at end of file
+#version 460 core
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+// 타일 번호마다 아틀라스 안의 texture 영역 (u0, v0, u1, v1) - texture_atlas.rs의 AtlasRegion
+layout (std430, binding = 0) readonly buffer AtlasRegions {
+    vec4 atlas_regions[];
+};
+
+// 위치, 빛, AO / 타일 번호, 위치의 소수 부분 / texture 좌표 (mesher.rs의 WORDS_PER_VERTEX 참고)
+layout (location = 0) in uvec3 packed_vertex;
+// 메시의 위치와 크기 (x, y, z, scale) - MeshArena가 그리기마다 하나씩 넘긴다
+// VAOs without this attribute (ChunkMesh) read the default (0, 0, 0, 1).
+layout (location = 1) in vec4 draw_offset;
+
+out VertexAttributes{
+    vec3 frag_pos;
+    vec2 texture_coords;
+    vec4 texture_rect;
+    vec2 light;
+    float ao;
+} attrs;
+
+// 1/16 블록 단위 (텍스쳐 픽셀 하나)
+const float SUBDIVISIONS = 16.0;
+const float MAX_AO = 3.0;
+const float MAX_LIGHT = 15.0;
+
+void main(){
+    uint word0 = packed_vertex.x;
+    uint word1 = packed_vertex.y;
+    uint word2 = packed_vertex.z;
+
+    uvec3 whole = uvec3(word0, word0 >> 5, word0 >> 10) & 31u;
+    uvec3 fraction = uvec3(word1 >> 12, word1 >> 16, word1 >> 20) & 15u;
+    vec3 pos = (vec3(whole) + vec3(fraction) / SUBDIVISIONS) * draw_offset.w + draw_offset.xyz;
+
+    gl_Position = projection * view * model * vec4(pos, 1.0f);
+
+    // frag 쉐이더는 위치만 필요하므로 vec3으로 정해줌
+    attrs.frag_pos = vec3(view * model * vec4(pos, 1.0f));
+
+    // 블록 단위 좌표 - 합쳐진 면에서도 블록마다 텍스쳐가 반복된다 (부호 있는 16비트 두 개)
+    ivec2 texture_coords = ivec2(int(word2 << 16) >> 16, int(word2) >> 16);
+    attrs.texture_coords = vec2(texture_coords) / SUBDIVISIONS;
+
+    attrs.texture_rect = atlas_regions[word1 & 4095u];
+
+    attrs.light = vec2((word0 >> 22) & 15u, (word0 >> 26) & 15u) / MAX_LIGHT;
+    attrs.ao = float((word0 >> 20) & 3u) / MAX_AO;
+}
@@ -1,4 +1,5 @@
 use crate::gl_call;
+use crate::texture_atlas::TextureAtlas;
 
 use gl;
 use image::ColorType;
@@ -55,3 +56,69 @@ pub fn create_texture(path: &str) -> u32 {
 
     id
 }
+
+// 아틀라스의 mipmap을 모두 올린 텍스쳐 - 단계마다 위아래를 뒤집어 GL의 왼쪽 아래 기준에 맞춘다
+pub fn create_atlas_texture(atlas: &TextureAtlas) -> u32 {
+    let mut id = 0;
+    gl_call!(gl::CreateTextures(gl::TEXTURE_2D, 1, &mut id));
+    gl_call!(gl::TextureParameteri(
+        id,
+        gl::TEXTURE_MIN_FILTER,
+        gl::NEAREST_MIPMAP_NEAREST as i32
+    ));
+    gl_call!(gl::TextureParameteri(
+        id,
+        gl::TEXTURE_MAG_FILTER,
+        gl::NEAREST as i32
+    ));
+    gl_call!(gl::TextureParameteri(
+        id,
+        gl::TEXTURE_MAX_LEVEL,
+        atlas.mip_levels() as i32 - 1
+    ));
+
+    gl_call!(gl::TextureStorage2D(
+        id,
+        atlas.mip_levels() as i32,
+        gl::RGBA8,
+        atlas.size() as i32,
+        atlas.size() as i32
+    ));
+
+    for level in 0..atlas.mip_levels() {
+        let img = image::imageops::flip_vertical(atlas.level(level));
+        gl_call!(gl::TextureSubImage2D(
+            id,
+            level as i32,
+            0,
+            0,
+            img.width() as i32,
+            img.height() as i32,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            img.as_raw().as_ptr() as *mut c_void
+        ));
+    }
+
+    id
+}
+
+// 타일 번호 순서의 texture 영역 - diffuse.vert의 AtlasRegions (storage buffer)
+pub fn create_atlas_region_buffer(atlas: &TextureAtlas) -> u32 {
+    // 빈 storage buffer는 만들 수 없다
+    let mut rects = atlas.region_rects();
+    if rects.is_empty() {
+        rects.push([0.0; 4]);
+    }
+
+    let mut id = 0;
+    gl_call!(gl::CreateBuffers(1, &mut id));
+    gl_call!(gl::NamedBufferStorage(
+        id,
+        (rects.len() * std::mem::size_of::<[f32; 4]>()) as isize,
+        rects.as_ptr() as *const c_void,
+        0
+    ));
+
+    id
+}
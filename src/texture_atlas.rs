@@ -0,0 +1,519 @@
+use image::{Rgba, RgbaImage};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+// packed vertex의 타일 번호가 12비트이므로 아틀라스에 넣을 수 있는 가장 많은 타일 수
+pub const MAX_REGIONS: usize = 1 << 12;
+
+pub type UVCoords = (f32, f32, f32, f32);
+
+#[derive(Clone, Debug)]
+pub struct AtlasSettings {
+    // 타일 둘레에 가장자리 픽셀을 늘여 채우는 폭 (픽셀) - mipmap 단계마다 절반으로 줄어든다
+    pub padding: u32,
+    // 원본을 포함한 mipmap 단계 수의 상한 - 가장 작은 타일이 1픽셀이 되면 더 만들지 않는다
+    pub max_mip_levels: u32,
+    // 아틀라스 한 변의 최대 크기 (픽셀)
+    pub max_size: u32,
+}
+
+// 아틀라스 안의 타일 하나
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub struct AtlasRegion {
+    // 타일 번호 - packed vertex에 들어가고 shader의 영역 목록에서 이 순서로 찾는다
+    pub index: u32,
+    // 가장자리를 뺀 타일의 픽셀 위치와 크기 (이미지의 왼쪽 위 기준)
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    // GL texture 좌표 (u0, v0, u1, v1) - 텍스쳐의 왼쪽 아래 기준
+    pub uv: UVCoords,
+}
+
+// 이름 붙인 이미지를 모아 아틀라스 하나로 합친다 - GL 없이 CPU에서만 동작한다
+pub struct TextureAtlasBuilder {
+    settings: AtlasSettings,
+    tiles: Vec<(String, RgbaImage)>,
+}
+
+impl TextureAtlasBuilder {
+    pub fn new(settings: AtlasSettings) -> Self {
+        Self {
+            settings,
+            tiles: Vec::new(),
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.tiles.iter().any(|(tile_name, _)| tile_name == name)
+    }
+
+    // 같은 이름의 타일은 한 번만 들어간다
+    pub fn add(&mut self, name: &str, image: RgbaImage) {
+        if !self.contains(name) {
+            self.tiles.push((name.to_string(), image));
+        }
+    }
+
+    // 파일 경로를 이름으로 쓴다
+    pub fn add_file(&mut self, path: &str) -> Result<(), String> {
+        if self.contains(path) {
+            return Ok(());
+        }
+
+        let image = image::open(path).map_err(|err| format!("Filename: {path}, error: {err}"))?;
+        self.add(path, image.to_rgba8());
+        Ok(())
+    }
+
+    // 타일을 높이 순으로 선반(shelf)에 늘어놓아 모두 들어가는 가장 작은 정사각형 아틀라스를 만든다
+    pub fn build(self) -> Result<TextureAtlas, String> {
+        if self.tiles.len() > MAX_REGIONS {
+            return Err(format!(
+                "Too many textures for the atlas: {} (at most {MAX_REGIONS})",
+                self.tiles.len()
+            ));
+        }
+        if let Some((name, _)) = self
+            .tiles
+            .iter()
+            .find(|(_, image)| image.width() == 0 || image.height() == 0)
+        {
+            return Err(format!("Texture '{name}' is empty"));
+        }
+
+        // 모든 타일의 크기가 2^(levels - 1)로 나누어떨어지는 만큼만 mipmap을 만든다
+        let levels = self
+            .tiles
+            .iter()
+            .map(|(_, image)| (image.width() | image.height()).trailing_zeros() + 1)
+            .chain([self.settings.max_mip_levels])
+            .min()
+            .unwrap()
+            .max(1);
+        let alignment = 1 << (levels - 1);
+
+        let area: u32 = self
+            .tiles
+            .iter()
+            .map(|(_, image)| {
+                let padded = |length: u32| round_up(length + self.settings.padding, alignment);
+                padded(image.width()) * padded(image.height())
+            })
+            .sum();
+        let mut size = ((area as f32).sqrt().ceil() as u32)
+            .next_power_of_two()
+            .max(alignment);
+
+        let positions = loop {
+            if size > self.settings.max_size {
+                return Err(format!(
+                    "The textures don't fit in a {0}x{0} atlas",
+                    self.settings.max_size
+                ));
+            }
+            if let Some(positions) = self.pack(size, alignment) {
+                break positions;
+            }
+            size *= 2;
+        };
+
+        let mut regions = HashMap::new();
+        let mut base = RgbaImage::new(size, size);
+        for (index, ((name, image), &(x, y))) in self.tiles.iter().zip(&positions).enumerate() {
+            image::imageops::replace(&mut base, image, x as i64, y as i64);
+
+            let (width, height) = image.dimensions();
+            let texture_size = size as f32;
+            let uv = (
+                x as f32 / texture_size,
+                (size - y - height) as f32 / texture_size,
+                (x + width) as f32 / texture_size,
+                (size - y) as f32 / texture_size,
+            );
+            regions.insert(
+                name.clone(),
+                AtlasRegion {
+                    index: index as u32,
+                    x,
+                    y,
+                    width,
+                    height,
+                    uv,
+                },
+            );
+        }
+
+        let mut atlas = TextureAtlas {
+            size,
+            padding: self.settings.padding,
+            levels: vec![base],
+            regions,
+        };
+        atlas.fill_gutters(0);
+        for level in 1..levels as usize {
+            atlas.build_mip_level(level);
+            atlas.fill_gutters(level);
+        }
+
+        Ok(atlas)
+    }
+
+    // 타일마다 가장자리를 뺀 위치 (x, y) - size x size 안에 다 들어가지 않으면 None
+    fn pack(&self, size: u32, alignment: u32) -> Option<Vec<(u32, u32)>> {
+        let padding = self.settings.padding;
+
+        let mut order: Vec<usize> = (0..self.tiles.len()).collect();
+        order.sort_by_key(|&i| {
+            let (width, height) = self.tiles[i].1.dimensions();
+            (std::cmp::Reverse(height), std::cmp::Reverse(width), i)
+        });
+
+        let mut positions = vec![(0, 0); self.tiles.len()];
+        // 선반의 타일이 시작하는 줄, 선반 아래쪽 가장자리가 끝나는 줄, 다음 타일의 가장자리가 시작하는 칸
+        let mut shelf_y = round_up(padding, alignment);
+        let (mut shelf_bottom, mut cursor) = (0, 0);
+
+        for i in order {
+            let (width, height) = self.tiles[i].1.dimensions();
+
+            let mut x = round_up(cursor + padding, alignment);
+            if x + width + padding > size && cursor > 0 {
+                shelf_y = round_up(shelf_bottom + padding, alignment);
+                x = round_up(padding, alignment);
+            }
+            if x + width + padding > size || shelf_y + height + padding > size {
+                return None;
+            }
+
+            positions[i] = (x, shelf_y);
+            cursor = x + width + padding;
+            shelf_bottom = shelf_bottom.max(shelf_y + height + padding);
+        }
+
+        Some(positions)
+    }
+}
+
+fn round_up(value: u32, alignment: u32) -> u32 {
+    value.div_ceil(alignment) * alignment
+}
+
+// 타일을 모두 합친 이미지와 mipmap, 이름마다 타일의 위치 (위쪽 줄부터 - 올릴 때 뒤집는다)
+pub struct TextureAtlas {
+    size: u32,
+    padding: u32,
+    levels: Vec<RgbaImage>,
+    regions: HashMap<String, AtlasRegion>,
+}
+
+// export가 이미지와 함께 저장하는 정보
+#[derive(Serialize)]
+struct AtlasMetadata<'a> {
+    size: u32,
+    padding: u32,
+    mip_levels: usize,
+    regions: BTreeMap<&'a str, AtlasRegion>,
+}
+
+impl TextureAtlas {
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn mip_levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    // level번째 mipmap - 0이 원본 크기
+    pub fn level(&self, level: usize) -> &RgbaImage {
+        &self.levels[level]
+    }
+
+    pub fn regions(&self) -> &HashMap<String, AtlasRegion> {
+        &self.regions
+    }
+
+    pub fn region(&self, name: &str) -> Option<AtlasRegion> {
+        self.regions.get(name).copied()
+    }
+
+    // 타일 번호 순서의 texture 좌표 (u0, v0, u1, v1) - shader가 타일 번호로 찾아 쓴다
+    pub fn region_rects(&self) -> Vec<[f32; 4]> {
+        let mut rects = vec![[0.0; 4]; self.regions.len()];
+        for region in self.regions.values() {
+            let (u0, v0, u1, v1) = region.uv;
+            rects[region.index as usize] = [u0, v0, u1, v1];
+        }
+        rects
+    }
+
+    // 확인용으로 원본 크기의 이미지를 PNG로, 타일 위치를 JSON으로 저장한다
+    pub fn export(&self, png_path: &str, json_path: &str) -> Result<(), String> {
+        self.levels[0]
+            .save(png_path)
+            .map_err(|err| format!("Failed to save {png_path}: {err}"))?;
+
+        let metadata = AtlasMetadata {
+            size: self.size,
+            padding: self.padding,
+            mip_levels: self.levels.len(),
+            regions: self
+                .regions
+                .iter()
+                .map(|(name, region)| (name.as_str(), *region))
+                .collect(),
+        };
+        let json = serde_json::to_string_pretty(&metadata).map_err(|err| err.to_string())?;
+        std::fs::write(json_path, json).map_err(|err| format!("Failed to save {json_path}: {err}"))
+    }
+
+    // 바로 위 단계의 타일마다 2x2 픽셀을 알파로 가중 평균해 하나로 줄인다
+    fn build_mip_level(&mut self, level: usize) {
+        let size = (self.size >> level).max(1);
+        let mut image = RgbaImage::new(size, size);
+        let source = &self.levels[level - 1];
+
+        for region in self.regions.values() {
+            let (x, y) = (region.x >> level, region.y >> level);
+            let (width, height) = (
+                (region.width >> level).max(1),
+                (region.height >> level).max(1),
+            );
+
+            for j in 0..height {
+                for i in 0..width {
+                    let (source_x, source_y) = ((x + i) * 2, (y + j) * 2);
+                    let pixels = [(0, 0), (1, 0), (0, 1), (1, 1)]
+                        .map(|(dx, dy)| source.get_pixel(source_x + dx, source_y + dy).0);
+
+                    let alpha: u32 = pixels.iter().map(|pixel| pixel[3] as u32).sum();
+                    let mut pixel = [0u8; 4];
+                    for channel in 0..3 {
+                        let weighted: u32 =
+                            pixels.iter().map(|p| p[channel] as u32 * p[3] as u32).sum();
+                        let sum = weighted.checked_div(alpha).unwrap_or_else(|| {
+                            pixels.iter().map(|p| p[channel] as u32).sum::<u32>() / 4
+                        });
+                        pixel[channel] = sum as u8;
+                    }
+                    pixel[3] = ((alpha + 2) / 4) as u8;
+
+                    image.put_pixel(x + i, y + j, Rgba(pixel));
+                }
+            }
+        }
+
+        self.levels.push(image);
+    }
+
+    // 타일 둘레의 가장자리를 타일의 가장 바깥 픽셀로 채운다
+    fn fill_gutters(&mut self, level: usize) {
+        let padding = self.padding >> level;
+        if padding == 0 {
+            return;
+        }
+
+        let image = &mut self.levels[level];
+        for region in self.regions.values() {
+            let (x, y) = ((region.x >> level) as i64, (region.y >> level) as i64);
+            let width = (region.width >> level).max(1) as i64;
+            let height = (region.height >> level).max(1) as i64;
+            let padding = padding as i64;
+
+            for j in y - padding..y + height + padding {
+                for i in x - padding..x + width + padding {
+                    let inside = (x..x + width).contains(&i) && (y..y + height).contains(&j);
+                    if inside
+                        || i < 0
+                        || j < 0
+                        || i >= image.width() as i64
+                        || j >= image.height() as i64
+                    {
+                        continue;
+                    }
+
+                    let nearest = (i.clamp(x, x + width - 1), j.clamp(y, y + height - 1));
+                    let pixel = *image.get_pixel(nearest.0 as u32, nearest.1 as u32);
+                    image.put_pixel(i as u32, j as u32, pixel);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> AtlasSettings {
+        AtlasSettings {
+            padding: 4,
+            max_mip_levels: 5,
+            max_size: 4096,
+        }
+    }
+
+    // 픽셀마다 다른 색의 타일 - 위치를 색으로 알아볼 수 있다
+    fn gradient(width: u32, height: u32, seed: u8) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            Rgba([(x * 8) as u8, (y * 8) as u8, seed, 255])
+        })
+    }
+
+    fn build(tiles: &[(&str, RgbaImage)]) -> TextureAtlas {
+        let mut builder = TextureAtlasBuilder::new(settings());
+        for (name, image) in tiles {
+            builder.add(name, image.clone());
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn packed_rects_dont_overlap() {
+        let sizes = [
+            (16, 16),
+            (32, 16),
+            (16, 48),
+            (64, 64),
+            (16, 16),
+            (48, 32),
+            (16, 32),
+        ];
+        let tiles: Vec<(String, RgbaImage)> = sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &(width, height))| (format!("tile{i}"), gradient(width, height, i as u8)))
+            .collect();
+        let tiles: Vec<(&str, RgbaImage)> = tiles
+            .iter()
+            .map(|(name, image)| (name.as_str(), image.clone()))
+            .collect();
+        let atlas = build(&tiles);
+
+        let padding = settings().padding;
+        let regions: Vec<AtlasRegion> = atlas.regions().values().copied().collect();
+        assert_eq!(regions.len(), sizes.len());
+        for (i, a) in regions.iter().enumerate() {
+            assert!(a.x >= padding && a.y >= padding);
+            assert!(a.x + a.width + padding <= atlas.size());
+            assert!(a.y + a.height + padding <= atlas.size());
+
+            // 가장자리까지 포함해도 겹치지 않는다
+            for b in &regions[..i] {
+                let apart = a.x >= b.x + b.width + 2 * padding
+                    || b.x >= a.x + a.width + 2 * padding
+                    || a.y >= b.y + b.height + 2 * padding
+                    || b.y >= a.y + a.height + 2 * padding;
+                assert!(apart, "{a:?} overlaps {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn gutters_copy_the_edge_texels() {
+        let tile = gradient(16, 16, 7);
+        let atlas = build(&[("tile", tile.clone())]);
+        let region = atlas.region("tile").unwrap();
+        let image = atlas.level(0);
+        let padding = settings().padding as i32;
+
+        for j in -padding..16 + padding {
+            for i in -padding..16 + padding {
+                let expected = tile.get_pixel(i.clamp(0, 15) as u32, j.clamp(0, 15) as u32);
+                let x = (region.x as i32 + i) as u32;
+                let y = (region.y as i32 + j) as u32;
+                assert_eq!(image.get_pixel(x, y), expected, "({i}, {j})");
+            }
+        }
+
+        // 한 단계 작은 mipmap의 가장자리는 절반 폭
+        let level = atlas.level(1);
+        let (x, y) = (region.x / 2, region.y / 2);
+        for i in 1..=padding as u32 / 2 {
+            assert_eq!(level.get_pixel(x - i, y + 3), level.get_pixel(x, y + 3));
+            assert_eq!(level.get_pixel(x + 7 + i, y), level.get_pixel(x + 7, y));
+        }
+    }
+
+    #[test]
+    fn mip_levels_average_by_alpha() {
+        // 체크무늬로 반은 투명한 검정, 반은 불투명한 빨강
+        let tile = RgbaImage::from_fn(16, 16, |x, y| {
+            if (x + y) % 2 == 0 {
+                Rgba([200, 0, 0, 255])
+            } else {
+                Rgba([0, 0, 0, 0])
+            }
+        });
+        let atlas = build(&[("tile", tile), ("solid", gradient(16, 16, 0))]);
+        assert_eq!(atlas.mip_levels(), 5);
+
+        let region = atlas.region("tile").unwrap();
+        for level in 1..atlas.mip_levels() {
+            let image = atlas.level(level);
+            assert_eq!(image.width(), atlas.size() >> level);
+
+            let (x, y) = (region.x >> level, region.y >> level);
+            for j in 0..16 >> level {
+                for i in 0..16 >> level {
+                    // 투명한 픽셀의 검정이 섞여 어두워지지 않는다
+                    assert_eq!(image.get_pixel(x + i, y + j).0, [200, 0, 0, 128]);
+                }
+            }
+        }
+
+        // 한 단계 위의 2x2 픽셀 평균
+        let region = atlas.region("solid").unwrap();
+        let (parent, child) = (atlas.level(0), atlas.level(1));
+        for j in 0..8 {
+            for i in 0..8 {
+                let pixels = [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| {
+                    parent
+                        .get_pixel(region.x + 2 * i + dx, region.y + 2 * j + dy)
+                        .0
+                });
+                let average = |channel: usize| {
+                    (pixels.iter().map(|p| p[channel] as u32).sum::<u32>() / 4) as u8
+                };
+                let pixel = child.get_pixel(region.x / 2 + i, region.y / 2 + j).0;
+                assert_eq!(pixel, [average(0), average(1), average(2), 255]);
+            }
+        }
+    }
+
+    #[test]
+    fn region_rects_match_the_flipped_upload() {
+        let atlas = build(&[
+            ("a", gradient(16, 16, 1)),
+            ("b", gradient(32, 16, 2)),
+            ("c", gradient(16, 32, 3)),
+        ]);
+        // texture::create_atlas_texture가 올리는 이미지 - 0번째 줄이 v = 0
+        let uploaded = image::imageops::flip_vertical(atlas.level(0));
+        let size = atlas.size() as f32;
+
+        let rects = atlas.region_rects();
+        for (name, seed) in [("a", 1), ("b", 2), ("c", 3)] {
+            let region = atlas.region(name).unwrap();
+            let [u0, v0, u1, v1] = rects[region.index as usize];
+            assert_eq!((u0, v0, u1, v1), region.uv);
+
+            let texel = |u: f32, v: f32| *uploaded.get_pixel((u * size) as u32, (v * size) as u32);
+            let (width, height) = (region.width as f32 / size, region.height as f32 / size);
+            assert_eq!((u1 - u0, v1 - v0), (width, height));
+
+            // texture 좌표 (0, 0)은 타일의 왼쪽 아래, (1, 1)에 가까운 곳은 오른쪽 위
+            let half = 0.5 / size;
+            let bottom_left = gradient(region.width, region.height, seed)
+                .get_pixel(0, region.height - 1)
+                .to_owned();
+            let top_right = gradient(region.width, region.height, seed)
+                .get_pixel(region.width - 1, 0)
+                .to_owned();
+            assert_eq!(texel(u0 + half, v0 + half), bottom_left);
+            assert_eq!(texel(u1 - half, v1 - half), top_right);
+        }
+    }
+}
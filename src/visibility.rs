@@ -0,0 +1,162 @@
+use crate::chunk_manager::CHUNK_SIZE;
+use crate::mesher::PaddedChunk;
+
+// 청크의 면 6개 - mesher의 Sides와 같은 순서 (반대쪽 면은 face ^ 1)
+pub const FACE_DIRECTIONS: [(i32, i32, i32); 6] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+pub fn opposite_face(face: usize) -> usize {
+    face ^ 1
+}
+
+// 청크 안의 투명한 블록을 따라 어느 면에서 어느 면으로 지나갈 수 있는지
+// connections[a]의 b번째 비트가 켜져 있으면 면 a로 들어온 시선이 면 b로 나갈 수 있다.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ChunkVisibility {
+    connections: [u8; 6],
+}
+
+impl ChunkVisibility {
+    // 모든 면이 서로 이어진 청크 (공기로만 된 청크, 아직 메시를 만들지 않은 청크)
+    pub const ALL: ChunkVisibility = ChunkVisibility {
+        connections: [0b111111; 6],
+    };
+    pub const NONE: ChunkVisibility = ChunkVisibility {
+        connections: [0; 6],
+    };
+
+    pub fn connects(&self, from: usize, to: usize) -> bool {
+        self.connections[from] & (1 << to) != 0
+    }
+
+    // 투명한 블록이 이어진 영역마다 flood fill로 그 영역이 닿는 면을 모아 서로 잇는다
+    pub fn compute(chunk: &PaddedChunk) -> ChunkVisibility {
+        let size = CHUNK_SIZE as i32;
+        let index = |x: i32, y: i32, z: i32| ((y * size + z) * size + x) as usize;
+        let is_open = |x: i32, y: i32, z: i32| chunk.get(x, y, z).is_transparent();
+
+        let mut visited = vec![false; (size * size * size) as usize];
+        let mut visibility = ChunkVisibility::NONE;
+        let mut stack = Vec::new();
+
+        for y in 0..size {
+            for z in 0..size {
+                for x in 0..size {
+                    if visited[index(x, y, z)] || !is_open(x, y, z) {
+                        continue;
+                    }
+
+                    let mut faces = 0u8;
+                    visited[index(x, y, z)] = true;
+                    stack.push((x, y, z));
+
+                    while let Some((x, y, z)) = stack.pop() {
+                        for (face, &(dx, dy, dz)) in FACE_DIRECTIONS.iter().enumerate() {
+                            let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+                            let outside = [nx, ny, nz].iter().any(|&c| c < 0 || c >= size);
+                            if outside {
+                                faces |= 1 << face;
+                                continue;
+                            }
+                            if visited[index(nx, ny, nz)] || !is_open(nx, ny, nz) {
+                                continue;
+                            }
+                            visited[index(nx, ny, nz)] = true;
+                            stack.push((nx, ny, nz));
+                        }
+                    }
+
+                    for face in 0..6 {
+                        if faces & (1 << face) != 0 {
+                            visibility.connections[face] |= faces;
+                        }
+                    }
+
+                    if visibility == ChunkVisibility::ALL {
+                        return visibility;
+                    }
+                }
+            }
+        }
+
+        visibility
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_registry::BLOCKS;
+    use crate::chunk::BlockID;
+
+    fn filled(block: BlockID) -> PaddedChunk {
+        let mut chunk = PaddedChunk::new();
+        let size = CHUNK_SIZE as i32;
+        for y in -1..=size {
+            for z in -1..=size {
+                for x in -1..=size {
+                    chunk.set(x, y, z, block);
+                }
+            }
+        }
+        chunk
+    }
+
+    fn connected_pairs(visibility: &ChunkVisibility) -> Vec<(usize, usize)> {
+        (0..6)
+            .flat_map(|from| (0..6).map(move |to| (from, to)))
+            .filter(|&(from, to)| from != to && visibility.connects(from, to))
+            .collect()
+    }
+
+    #[test]
+    fn solid_chunk_connects_nothing() {
+        let visibility = ChunkVisibility::compute(&filled(BLOCKS.id("stone")));
+        assert_eq!(visibility, ChunkVisibility::NONE);
+    }
+
+    #[test]
+    fn air_chunk_connects_everything() {
+        let visibility = ChunkVisibility::compute(&filled(BlockID::AIR));
+        assert_eq!(visibility, ChunkVisibility::ALL);
+        assert_eq!(connected_pairs(&visibility).len(), 30);
+    }
+
+    #[test]
+    fn tunnel_connects_its_ends() {
+        let mut chunk = filled(BLOCKS.id("stone"));
+        for x in 0..CHUNK_SIZE as i32 {
+            chunk.set(x, 8, 8, BlockID::AIR);
+        }
+
+        // right (0)과 left (1)만 이어진다
+        let visibility = ChunkVisibility::compute(&chunk);
+        assert_eq!(connected_pairs(&visibility), vec![(0, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn sealed_pockets_stay_apart() {
+        let mut chunk = filled(BLOCKS.id("stone"));
+        // right 면에 닿는 공간과 top 면에 닿는 공간 - 서로 돌로 막혀 있다
+        for y in 2..5 {
+            for z in 2..5 {
+                chunk.set(15, y, z, BlockID::AIR);
+                chunk.set(14, y, z, BlockID::AIR);
+            }
+        }
+        for x in 8..11 {
+            chunk.set(x, 15, 8, BlockID::AIR);
+        }
+
+        let visibility = ChunkVisibility::compute(&chunk);
+        assert!(!visibility.connects(0, 2));
+        assert!(!visibility.connects(2, 0));
+        assert!(connected_pairs(&visibility).is_empty());
+    }
+}
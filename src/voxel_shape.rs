@@ -0,0 +1,82 @@
+use crate::aabb::AABB;
+use nalgebra_glm::{vec3, Vec3};
+use serde::Deserialize;
+
+// 블록 한 칸 안의 모양 - 블록 좌표 (0..1) 기준의 상자 여러 개
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(from = "Vec<[f32; 6]>")]
+pub struct VoxelShape {
+    pub boxes: Vec<AABB>,
+}
+
+lazy_static! {
+    pub static ref FULL_SHAPE: VoxelShape = VoxelShape {
+        boxes: vec![AABB::new(vec3(0.0, 0.0, 0.0), vec3(1.0, 1.0, 1.0))],
+    };
+    pub static ref EMPTY_SHAPE: VoxelShape = VoxelShape::default();
+}
+
+impl From<Vec<[f32; 6]>> for VoxelShape {
+    fn from(boxes: Vec<[f32; 6]>) -> Self {
+        Self {
+            boxes: boxes
+                .into_iter()
+                .map(|[x0, y0, z0, x1, y1, z1]| AABB::new(vec3(x0, y0, z0), vec3(x1, y1, z1)))
+                .collect(),
+        }
+    }
+}
+
+impl VoxelShape {
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+
+    // 블록 (x, y, z)에 놓였을 때의 상자들 (월드 좌표)
+    pub fn at(&self, x: i32, y: i32, z: i32) -> impl Iterator<Item = AABB> + '_ {
+        let offset = vec3(x as f32, y as f32, z as f32);
+        self.boxes
+            .iter()
+            .map(move |aabb| AABB::new(aabb.mins + offset, aabb.maxs + offset))
+    }
+
+    // 블록 (x, y, z)에 놓인 이 모양에 광선이 처음 닿는 거리와 그 면의 축
+    pub fn ray_hit(
+        &self,
+        x: i32,
+        y: i32,
+        z: i32,
+        origin: &Vec3,
+        direction: &Vec3,
+    ) -> Option<(f32, Option<usize>)> {
+        self.at(x, y, z)
+            .filter_map(|aabb| aabb.ray_intersection(origin, direction))
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+    }
+
+    // 상자의 두 모서리를 옮긴 뒤 다시 최소, 최대 모서리로 정리한다 (회전, 뒤집기)
+    pub fn transformed(&self, f: impl Fn(Vec3) -> Vec3) -> VoxelShape {
+        VoxelShape {
+            boxes: self
+                .boxes
+                .iter()
+                .map(|aabb| {
+                    let (a, b) = (f(aabb.mins), f(aabb.maxs));
+                    AABB::new(a.inf(&b), a.sup(&b))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for aabb in &self.boxes {
+            if (0..3).any(|axis| aabb.mins[axis] >= aabb.maxs[axis]) {
+                return Err(format!(
+                    "Empty box {:?}..{:?} in a block shape",
+                    aabb.mins, aabb.maxs
+                ));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,518 @@
+use crate::biome::{Biome, BiomeMap};
+use crate::block_registry::BLOCKS;
+use crate::chunk::BlockID;
+use crate::chunk_manager::CHUNK_SIZE;
+use crate::features::FeatureRegistry;
+use noise::{NoiseFn, SuperSimplex};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+// 지표면 근처를 3D 노이즈로 흔들어 절벽과 돌출부를 만든다
+#[derive(Clone, Debug)]
+pub struct TerrainSettings {
+    // 높이맵에서 위아래로 최대 몇 블록까지 흔들리는지 (0이면 순수한 높이맵)
+    pub overhang_amplitude: f64,
+    pub overhang_scale: f64,
+    // 이 높이까지 지표면 위의 빈 곳을 물로 채운다
+    pub sea_level: i32,
+}
+
+// 굴은 노이즈 두 개가 모두 0에 가까운 곳에, 큰 동굴은 세 번째 노이즈가 높은 곳에 판다
+#[derive(Clone, Debug)]
+pub struct CaveSettings {
+    pub enabled: bool,
+    pub min_y: i32,
+    pub tunnel_scale: f64,
+    // 클수록 굴이 굵어진다
+    pub tunnel_radius: f64,
+    pub cavern_scale: f64,
+    // 클수록 큰 동굴이 드물어진다
+    pub cavern_threshold: f64,
+    // 큰 동굴은 지표면에서 이만큼 아래에서만 생긴다
+    pub cavern_depth: i32,
+}
+
+#[derive(Clone, Debug)]
+pub struct OreSettings {
+    pub block: BlockID,
+    pub min_y: i32,
+    pub max_y: i32,
+    pub scale: f64,
+    // 클수록 광맥이 드물고 작아진다
+    pub threshold: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct WorldGenSettings {
+    pub terrain: TerrainSettings,
+    pub caves: CaveSettings,
+    pub ores: Vec<OreSettings>,
+}
+
+impl Default for WorldGenSettings {
+    fn default() -> Self {
+        let ore = |name: &str, max_y: i32, threshold: f64| OreSettings {
+            block: BLOCKS.id(name),
+            min_y: 1,
+            max_y,
+            scale: 4.0,
+            threshold,
+        };
+
+        Self {
+            terrain: TerrainSettings {
+                overhang_amplitude: 6.0,
+                overhang_scale: 24.0,
+                sea_level: 17,
+            },
+            caves: CaveSettings {
+                enabled: true,
+                min_y: 1,
+                tunnel_scale: 48.0,
+                tunnel_radius: 0.06,
+                cavern_scale: 32.0,
+                cavern_threshold: 0.65,
+                cavern_depth: 8,
+            },
+            ores: vec![
+                ore("coal_ore", 64, 0.75),
+                ore("iron_ore", 40, 0.8),
+                ore("gold_ore", 20, 0.85),
+                ore("diamond_ore", 12, 0.88),
+            ],
+        }
+    }
+}
+
+// splitmix64 - 시드에 값을 섞어 서로 관계없는 새 시드를 만든다
+pub fn mix_seed(seed: u64, value: u64) -> u64 {
+    let mut z = seed ^ value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// 노이즈마다 다른 시드를 쓴다 (salt로 구분)
+fn noise_seed(seed: u64, salt: u64) -> u32 {
+    mix_seed(seed, salt) as u32
+}
+
+// 생성 중인 청크 기둥 - 불러오지 않은 곳이면 get_block이 None
+pub trait ColumnAccess {
+    fn get_block(&self, x: i32, y: i32, z: i32) -> Option<BlockID>;
+    fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockID);
+}
+
+// 생성 결과는 시드와 위치로만 정해진다 - 기둥을 어떤 순서로, 어느 스레드에서 만들어도 같다
+pub struct WorldGenerator {
+    seed: u64,
+    settings: WorldGenSettings,
+    noise: SuperSimplex,
+    biomes: BiomeMap,
+    overhang_noise: SuperSimplex,
+    tunnel_noise: [SuperSimplex; 2],
+    cavern_noise: SuperSimplex,
+    ore_noise: Vec<SuperSimplex>,
+    features: FeatureRegistry,
+    stone: BlockID,
+    water: BlockID,
+}
+
+impl WorldGenerator {
+    pub fn new(seed: u64) -> Self {
+        WorldGenerator::with_settings(seed, WorldGenSettings::default())
+    }
+
+    pub fn with_settings(seed: u64, settings: WorldGenSettings) -> Self {
+        let ore_noise = (0..settings.ores.len() as u64)
+            .map(|i| SuperSimplex::new(noise_seed(seed, 100 + i)))
+            .collect();
+
+        Self {
+            seed,
+            settings,
+            noise: SuperSimplex::new(noise_seed(seed, 0)),
+            biomes: BiomeMap::new(noise_seed(seed, 1), noise_seed(seed, 2)),
+            overhang_noise: SuperSimplex::new(noise_seed(seed, 3)),
+            tunnel_noise: [
+                SuperSimplex::new(noise_seed(seed, 4)),
+                SuperSimplex::new(noise_seed(seed, 5)),
+            ],
+            cavern_noise: SuperSimplex::new(noise_seed(seed, 6)),
+            ore_noise,
+            features: FeatureRegistry::with_default_features(),
+            stone: BLOCKS.id("stone"),
+            water: BLOCKS.id("water"),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // 청크 기둥마다 시드와 좌표로 정해지는 난수 생성기 (salt로 용도마다 다른 난수열을 쓴다)
+    pub fn column_rng(&self, chunk_x: i32, chunk_z: i32, salt: u64) -> ChaCha8Rng {
+        let coords = ((chunk_x as u32 as u64) << 32) | chunk_z as u32 as u64;
+        ChaCha8Rng::seed_from_u64(mix_seed(mix_seed(self.seed, coords), salt))
+    }
+
+    pub fn features(&self) -> &FeatureRegistry {
+        &self.features
+    }
+
+    pub fn features_mut(&mut self) -> &mut FeatureRegistry {
+        &mut self.features
+    }
+
+    pub fn settings(&self) -> &WorldGenSettings {
+        &self.settings
+    }
+
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome {
+        self.biomes.biome_at(x, z)
+    }
+
+    // 주변 바이옴들의 높이 설정을 섞어서 경계에서도 지형이 끊기지 않게 한다
+    pub fn height_at(&self, x: i32, z: i32) -> i32 {
+        let (xf, zf) = (x as f64 / 64.0, z as f64 / 64.0);
+        let y = self.noise.get([xf, zf]);
+
+        let height: f64 = self
+            .biomes
+            .weights_at(x, z)
+            .iter()
+            .map(|(biome, weight)| {
+                let settings = biome.settings();
+                weight * (settings.base_height + settings.height_scale * y)
+            })
+            .sum();
+
+        height as i32
+    }
+
+    fn sample(noise: &SuperSimplex, x: i32, y: i32, z: i32, scale: f64) -> f64 {
+        noise.get([x as f64 / scale, y as f64 / scale, z as f64 / scale])
+    }
+
+    // 높이맵 + 3D 노이즈로 정한 지형 (동굴은 아직 파지 않은 상태)
+    fn is_terrain(&self, x: i32, y: i32, z: i32, height: i32) -> bool {
+        let terrain = &self.settings.terrain;
+        let distance = (height - y) as f64;
+
+        if distance > terrain.overhang_amplitude {
+            return true;
+        }
+        if distance < -terrain.overhang_amplitude {
+            return false;
+        }
+
+        let density = distance
+            + terrain.overhang_amplitude
+                * WorldGenerator::sample(&self.overhang_noise, x, y, z, terrain.overhang_scale);
+        density > 0.0
+    }
+
+    fn is_cave(&self, x: i32, y: i32, z: i32, height: i32) -> bool {
+        let caves = &self.settings.caves;
+        if !caves.enabled || y < caves.min_y {
+            return false;
+        }
+
+        let tunnel = self.tunnel_noise.iter().all(|noise| {
+            WorldGenerator::sample(noise, x, y, z, caves.tunnel_scale).abs() < caves.tunnel_radius
+        });
+        if tunnel {
+            return true;
+        }
+
+        y < height - caves.cavern_depth
+            && WorldGenerator::sample(&self.cavern_noise, x, y, z, caves.cavern_scale)
+                > caves.cavern_threshold
+    }
+
+    // 높이 범위가 맞는 광석 중 노이즈 값이 기준을 넘는 첫 번째 광석
+    fn ore_at(&self, x: i32, y: i32, z: i32) -> Option<BlockID> {
+        self.settings
+            .ores
+            .iter()
+            .zip(self.ore_noise.iter())
+            .find(|(ore, noise)| {
+                (ore.min_y..=ore.max_y).contains(&y)
+                    && WorldGenerator::sample(noise, x, y, z, ore.scale) > ore.threshold
+            })
+            .map(|(ore, _)| ore.block)
+    }
+
+    // 가장 위의 지표면 블록과 그 블록 (동굴이 지표면을 뚫었거나 물에 잠겼다면 None)
+    pub fn surface_at(&self, x: i32, z: i32) -> Option<(i32, BlockID)> {
+        let height = self.height_at(x, z);
+        let overhang = self.settings.terrain.overhang_amplitude.ceil() as i32;
+
+        let y = (0..=height + overhang)
+            .rev()
+            .find(|&y| self.is_terrain(x, y, z, height))?;
+        if self.is_cave(x, y, z, height) || y < self.settings.terrain.sea_level {
+            return None;
+        }
+
+        Some((y, self.biome_at(x, z).settings().surface))
+    }
+
+    // 멀리 있는 지형(LOD)에 쓰는 지표면의 높이와 블록 - 높이맵만 보고 돌출부와 동굴은 무시한다
+    pub fn lod_surface_at(&self, x: i32, z: i32) -> (i32, BlockID) {
+        let height = self.height_at(x, z);
+        let sea_level = self.settings.terrain.sea_level;
+
+        if height < sea_level {
+            (sea_level, self.water)
+        } else {
+            (height, self.biome_at(x, z).settings().surface)
+        }
+    }
+
+    // 청크 기둥 하나(x, z가 같은 청크 전체)의 지형을 생성하고 구조물을 배치한다
+    pub fn generate_column(&self, chunk_x: i32, chunk_z: i32, world: &mut dyn ColumnAccess) {
+        let size = CHUNK_SIZE as i32;
+        let overhang = self.settings.terrain.overhang_amplitude.ceil() as i32;
+
+        for x in chunk_x * size..(chunk_x + 1) * size {
+            for z in chunk_z * size..(chunk_z + 1) * size {
+                let height = self.height_at(x, z);
+                let biome = self.biome_at(x, z).settings();
+
+                // 위에서부터 내려가며 바로 위가 하늘로 트인 블록부터 표면, 그 아래 흙, 그 아래 돌을 채운다
+                let sea_level = self.settings.terrain.sea_level;
+                let mut depth = 0;
+                let mut open_sky = true;
+                for y in (0..=height.max(sea_level) + overhang).rev() {
+                    if !self.is_terrain(x, y, z, height) {
+                        // 물은 하늘로 트인 곳에만 채운다 - 동굴은 비어 있다
+                        if y <= sea_level && open_sky {
+                            world.set_block(x, y, z, self.water);
+                        }
+                        depth = 0;
+                        continue;
+                    }
+
+                    // 물에 잠긴 바닥에는 풀 대신 그 아래 흙을 깐다
+                    let block = if depth == 0 && y < sea_level {
+                        biome.subsurface
+                    } else if depth == 0 {
+                        biome.surface
+                    } else if depth <= biome.subsurface_depth {
+                        biome.subsurface
+                    } else {
+                        self.stone
+                    };
+                    depth += 1;
+                    open_sky = false;
+
+                    if self.is_cave(x, y, z, height) {
+                        continue;
+                    }
+
+                    let block = if block == self.stone {
+                        self.ore_at(x, y, z).unwrap_or(block)
+                    } else {
+                        block
+                    };
+                    world.set_block(x, y, z, block);
+                }
+            }
+        }
+
+        self.features.decorate(self, chunk_x, chunk_z, world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::WORLD_HEIGHT_CHUNKS;
+    use crate::features::{Anchor, Density, Feature, FeatureWriter, Placement};
+    use std::collections::HashMap;
+
+    const WORLD_HEIGHT: i32 = WORLD_HEIGHT_CHUNKS * CHUNK_SIZE as i32;
+
+    // 생성된 기둥들을 모아 두는 월드 - 쓰기는 지금 생성 중인 기둥 안에서만 된다
+    #[derive(Default)]
+    struct TestWorld {
+        column: (i32, i32),
+        blocks: HashMap<(i32, i32, i32), BlockID>,
+    }
+
+    impl TestWorld {
+        fn generate(&mut self, generator: &WorldGenerator, column: (i32, i32)) {
+            self.column = column;
+            generator.generate_column(column.0, column.1, self);
+        }
+
+        fn block(&self, x: i32, y: i32, z: i32) -> BlockID {
+            self.blocks.get(&(x, y, z)).copied().unwrap_or(BlockID::AIR)
+        }
+
+        // 기둥 안의 블록을 모두 정해진 순서로 섞는다 - 플랫폼과 상관없이 같은 값이 나온다
+        fn column_hash(&self, (chunk_x, chunk_z): (i32, i32)) -> u64 {
+            let size = CHUNK_SIZE as i32;
+            let mut hash = 0;
+            for x in chunk_x * size..(chunk_x + 1) * size {
+                for z in chunk_z * size..(chunk_z + 1) * size {
+                    for y in 0..WORLD_HEIGHT {
+                        hash = mix_seed(hash, self.block(x, y, z).0 as u64);
+                    }
+                }
+            }
+            hash
+        }
+    }
+
+    impl ColumnAccess for TestWorld {
+        fn get_block(&self, x: i32, y: i32, z: i32) -> Option<BlockID> {
+            let size = CHUNK_SIZE as i32;
+            let inside = (x.div_euclid(size), z.div_euclid(size)) == self.column;
+            (inside && (0..WORLD_HEIGHT).contains(&y)).then(|| self.block(x, y, z))
+        }
+
+        fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockID) {
+            if self.get_block(x, y, z).is_some() {
+                self.blocks.insert((x, y, z), block);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_columns_match_golden_hashes() {
+        // 생성 결과가 바뀌면 이 값들도 바뀐다 - 의도한 변경일 때만 고친다
+        let golden = [
+            (0, (0, 0), 0xeee6_3f85_bab1_e0d6),
+            (0, (-2, 3), 0xf11c_2bbb_9a2a_a5fa),
+            (1296, (0, 0), 0xd4a8_acf2_6c27_f3ed),
+            (1296, (5, -7), 0xb476_890a_62e9_db73),
+            (0xDEAD_BEEF, (0, 0), 0xde29_9e45_447b_8a52),
+            (0xDEAD_BEEF, (-1, -1), 0x68fd_4524_ce60_a985),
+        ];
+
+        for (seed, column, expected) in golden {
+            let generator = WorldGenerator::new(seed);
+            let mut world = TestWorld::default();
+            world.generate(&generator, column);
+            let hash = world.column_hash(column);
+            assert_eq!(hash, expected, "Column {column:?} of seed {seed}");
+        }
+    }
+
+    #[test]
+    fn generation_order_does_not_change_the_world() {
+        let columns: Vec<(i32, i32)> = (-1..=1)
+            .flat_map(|x| (-1..=1).map(move |z| (x, z)))
+            .collect();
+        let mut reordered = columns.clone();
+        reordered.reverse();
+        reordered.swap(1, 5);
+
+        let generate = |order: &[(i32, i32)]| {
+            let generator = WorldGenerator::new(42);
+            let mut world = TestWorld::default();
+            for &column in order {
+                world.generate(&generator, column);
+            }
+            world
+        };
+        let first = generate(&columns);
+        let second = generate(&reordered);
+        // 구조물이 없으면 순서에 따라 달라질 것도 없다
+        let leaves = BLOCKS.id("oak_leaves");
+        assert!(first.blocks.values().any(|&block| block == leaves));
+
+        for &column in &columns {
+            assert_eq!(
+                first.column_hash(column),
+                second.column_hash(column),
+                "Column {column:?}"
+            );
+        }
+        assert_eq!(first.blocks, second.blocks);
+    }
+
+    // 하늘에 걸친 한 줄 - 양옆 기둥까지 닿고, 블록으로 어느 기둥에서 왔는지 알 수 있다
+    struct Beam;
+
+    impl Feature for Beam {
+        fn radius(&self) -> i32 {
+            CHUNK_SIZE as i32
+        }
+
+        fn place(
+            &self,
+            (x, y, z): (i32, i32, i32),
+            _: &mut ChaCha8Rng,
+            writer: &mut FeatureWriter,
+        ) {
+            let column = x.div_euclid(CHUNK_SIZE as i32);
+            let block = BLOCKS.id(if column % 2 == 0 { "debug" } else { "obsidian" });
+            for dx in -self.radius()..=self.radius() {
+                writer.place(x + dx, y, z, block);
+            }
+        }
+    }
+
+    #[test]
+    fn features_along_a_border_match_in_either_order() {
+        let (a, b) = ((1, 0), (2, 0));
+        let generate = |order: [(i32, i32); 2]| {
+            let mut generator = WorldGenerator::new(1296);
+            let beam = Placement {
+                attempts: 1,
+                density: Density::Fixed(1.0),
+                anchor: Anchor::Underground {
+                    min_y: 200,
+                    max_y: 200,
+                },
+                biomes: Vec::new(),
+                ground: Vec::new(),
+            };
+            generator.features_mut().register("beam", Beam, beam);
+            let mut world = TestWorld::default();
+            for column in order {
+                world.generate(&generator, column);
+            }
+            world
+        };
+        let a_then_b = generate([a, b]);
+        let b_then_a = generate([b, a]);
+
+        // 두 기둥의 경계에서 양쪽으로 구조물이 닿을 수 있는 만큼
+        let size = CHUNK_SIZE as i32;
+        let border = b.0 * size;
+        let leaves = BLOCKS.id("oak_leaves");
+        let mut features = 0;
+        for x in border - 3..border + 3 {
+            for z in a.1 * size..(a.1 + 1) * size {
+                for y in 0..WORLD_HEIGHT {
+                    let block = a_then_b.block(x, y, z);
+                    assert_eq!(block, b_then_a.block(x, y, z), "Block ({x}, {y}, {z})");
+                    features += (block == leaves) as usize;
+                }
+            }
+        }
+        assert!(features > 0, "No tree reaches the border");
+
+        // 두 순서 모두, 나중에 만든 기둥과 먼저 만든 기둥이 서로 넘친 블록을 받았다
+        let beam_in = |world: &TestWorld, (chunk_x, chunk_z): (i32, i32), name: &str| {
+            let xs = chunk_x * size..(chunk_x + 1) * size;
+            let zs = chunk_z * size..(chunk_z + 1) * size;
+            xs.flat_map(|x| zs.clone().map(move |z| (x, z)))
+                .any(|(x, z)| world.block(x, 200, z) == BLOCKS.id(name))
+        };
+        for world in [&a_then_b, &b_then_a] {
+            assert!(
+                beam_in(world, b, "obsidian"),
+                "Column {b:?} is missing blocks from {a:?}"
+            );
+            assert!(
+                beam_in(world, a, "debug"),
+                "Column {a:?} is missing blocks from {b:?}"
+            );
+        }
+    }
+}